@@ -6,83 +6,748 @@
 //! to calcualte the game physics and return objects to render on the JS side.
 
 use engine_rs::{
-    board::Board,
+    board::{Board, State},
+    config::{BallCollisions, Boundaries, GameConfig, GameConfigBuilder},
+    event::{BounceKind, Event},
     game::{Game, Position},
+    replay::{Input, ReplayRecorder},
+    score::GameStatus,
+    Error,
 };
 
-use wasm_bindgen::prelude::wasm_bindgen;
+use std::fmt;
+
+use wasm_bindgen::prelude::{wasm_bindgen, JsError};
+
+#[wasm_bindgen]
+#[derive(Clone, Copy)]
+/// Position and team of a single ball.
+pub struct BallObject {
+    /// Ball X coordinate (negative for images beyond the left edge).
+    pub x: i32,
+    /// Ball Y coordinate (negative for images beyond the top edge).
+    pub y: i32,
+    /// Team id of the ball (`1` for lit, `0` for dark, see [`State::id`]).
+    pub team: u8,
+}
 
 #[wasm_bindgen]
+#[derive(Clone, Copy)]
+/// Position and team of a single paddle.
+pub struct PaddleObject {
+    /// X coordinate of the paddle center.
+    pub x: u32,
+    /// Y coordinate of the paddle center.
+    pub y: u32,
+    /// Team id of the paddle.
+    pub team: u8,
+}
+
+#[wasm_bindgen(getter_with_clone)]
 /// Positions and sizes of game objects.
 pub struct GameObjects {
-    /// Lit ball X coordinate.
-    pub lit_ball_x: u32,
-    /// Lit ball Y coordinate.
-    pub lit_ball_y: u32,
-    /// Dark ball X coordinate.
-    pub dark_ball_x: u32,
-    /// Dark ball Y coordinate.
-    pub dark_ball_y: u32,
+    /// All the balls in the game.
+    pub balls: Vec<BallObject>,
+    /// All the paddles in the game.
+    pub paddles: Vec<PaddleObject>,
     /// Cell width.
     pub cell_size_x: u32,
     /// Cell height.
     pub cell_size_y: u32,
     /// Radius of the ball
     pub ball_radius: u32,
+    /// Paddle width.
+    pub paddle_width: u32,
+    /// Paddle height.
+    pub paddle_height: u32,
+}
+
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+/// Kind of the game event (see [`Event`]).
+pub enum EventKind {
+    /// A cell was converted to `team`.
+    CellFlipped,
+    /// The `ball` bounced off the edge of the viewport.
+    WallBounce,
+    /// The `ball` bounced off a cell of another team.
+    CellBounce,
+    /// The `ball` bounced off a wall cell.
+    ObstacleBounce,
+    /// The `ball` bounced off a paddle.
+    PaddleBounce,
+    /// The `ball` bounced off the `other` ball.
+    BallCollision,
+    /// The `ball` touched the absorbing edge of the viewport.
+    BallAbsorbed,
+    /// The `ball` changed its `speed`.
+    SpeedChanged,
+    /// The game is over (see [`WasmGame::winner`]).
+    GameFinished,
+}
+
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+/// The way the balls interact with each other (see [`BallCollisions`]).
+pub enum BallCollisionsMode {
+    /// The balls pass through each other.
+    Off,
+    /// All the balls bounce off each other.
+    Bounce,
+    /// Only the balls of the same team bounce off each other.
+    SameTeamOnly,
+}
+
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+/// The way the balls interact with the edges of the viewport (see [`Boundaries`]).
+pub enum BoundariesMode {
+    /// The balls bounce off the edges.
+    Reflect,
+    /// The balls come back through the opposite edge.
+    Wrap,
+    /// A ball touching an edge ends the round.
+    Absorb,
+}
+
+#[wasm_bindgen]
+#[derive(Clone, Copy)]
+/// A single game event.
+///
+/// Only the fields relevant to the `kind` of the event are set,
+/// the other ones are zeroed.
+pub struct EventObject {
+    /// Kind of the event.
+    pub kind: EventKind,
+    /// Index of the ball.
+    pub ball: u32,
+    /// Index of the other ball of the collision.
+    pub other: u32,
+    /// Row of the flipped cell.
+    pub row: u16,
+    /// Column of the flipped cell.
+    pub col: u16,
+    /// Team id the cell was flipped to.
+    pub team: u8,
+    /// Ball X coordinate at the bounce (the point of contact for collisions).
+    pub x: u32,
+    /// Ball Y coordinate at the bounce (the point of contact for collisions).
+    pub y: u32,
+    /// New speed of the ball (pixels per millisecond).
+    pub speed: f32,
+}
+
+impl From<Event> for EventObject {
+    fn from(event: Event) -> Self {
+        let empty = Self {
+            kind: EventKind::CellFlipped,
+            ball: 0,
+            other: 0,
+            row: 0,
+            col: 0,
+            team: 0,
+            x: 0,
+            y: 0,
+            speed: 0.0,
+        };
+        match event {
+            Event::CellFlipped { row, col, by_team } => Self {
+                row,
+                col,
+                team: by_team.id(),
+                ..empty
+            },
+            Event::BallBounced {
+                ball,
+                kind,
+                position,
+            } => Self {
+                kind: match kind {
+                    BounceKind::Wall => EventKind::WallBounce,
+                    BounceKind::Cell => EventKind::CellBounce,
+                    BounceKind::Obstacle => EventKind::ObstacleBounce,
+                    BounceKind::Paddle => EventKind::PaddleBounce,
+                },
+                ball: ball as _,
+                x: position.x as _,
+                y: position.y as _,
+                ..empty
+            },
+            Event::BallsCollided {
+                ball,
+                other,
+                position,
+            } => Self {
+                kind: EventKind::BallCollision,
+                ball: ball as _,
+                other: other as _,
+                x: position.x as _,
+                y: position.y as _,
+                ..empty
+            },
+            Event::BallAbsorbed { ball, position } => Self {
+                kind: EventKind::BallAbsorbed,
+                ball: ball as _,
+                x: position.x as _,
+                y: position.y as _,
+                ..empty
+            },
+            Event::SpeedChanged { ball, speed } => Self {
+                kind: EventKind::SpeedChanged,
+                ball: ball as _,
+                speed,
+                ..empty
+            },
+            Event::GameFinished { .. } => Self {
+                kind: EventKind::GameFinished,
+                ..empty
+            },
+        }
+    }
+}
+
+#[wasm_bindgen]
+#[derive(Clone, Copy)]
+/// Dimensions of the board (in cells).
+pub struct BoardSize {
+    /// Number of columns.
+    pub width: u16,
+    /// Number of rows.
+    pub height: u16,
+}
+
+#[wasm_bindgen]
+#[derive(Clone, Copy)]
+/// Score of a single team.
+pub struct ScoreObject {
+    /// Team id.
+    pub team: u8,
+    /// Number of cells owned by the team.
+    pub cells: u32,
+    /// Number of cells flipped by the balls of the team.
+    pub flips: u32,
+}
+
+#[wasm_bindgen]
+/// Gameplay configuration (see [`GameConfig`]).
+///
+/// All the values default to the classic gameplay.
+pub struct WasmGameConfig {
+    builder: GameConfigBuilder,
+}
+
+#[wasm_bindgen]
+impl WasmGameConfig {
+    /// Create a configuration with default values.
+    pub fn new() -> Self {
+        Self {
+            builder: GameConfig::builder(),
+        }
+    }
+
+    /// Set the length of a single physics step (milliseconds).
+    pub fn step_ms(&mut self, step_ms: u64) {
+        self.update(|b| b.step_ms(step_ms));
+    }
+
+    /// Set the speed of newly launched balls (pixels per millisecond).
+    pub fn initial_speed(&mut self, speed: f32) {
+        self.update(|b| b.initial_speed(speed));
+    }
+
+    /// Set the speed the balls can accelerate to (pixels per millisecond).
+    pub fn max_speed(&mut self, speed: f32) {
+        self.update(|b| b.max_speed(speed));
+    }
+
+    /// Set the speed gained on every bounce (pixels per millisecond).
+    pub fn speed_up(&mut self, speed_up: f32) {
+        self.update(|b| b.speed_up(speed_up));
+    }
+
+    /// Set the initial heading of the ball of given team (degrees, clockwise from right).
+    ///
+    /// Throws if the team id is not supported.
+    pub fn initial_angle(&mut self, team: u8, angle: f32) -> Result<(), JsError> {
+        let team = State::try_team(team)?;
+        self.update(|b| b.initial_angle(team, angle));
+        Ok(())
+    }
+
+    /// Set the ball radius relative to the average cell dimension.
+    pub fn ball_radius_ratio(&mut self, ratio: f32) {
+        self.update(|b| b.ball_radius_ratio(ratio));
+    }
+
+    /// Set the skew of the rebound angle at the initial speed (degrees).
+    pub fn angle_skew(&mut self, skew: f32) {
+        self.update(|b| b.angle_skew(skew));
+    }
+
+    /// Set the seed randomizing the start positions and headings of the balls.
+    pub fn seed(&mut self, seed: u64) {
+        self.update(|b| b.seed(seed));
+    }
+
+    /// Set the maximal random deviation of the rebound angle (degrees).
+    pub fn bounce_jitter(&mut self, jitter: f32) {
+        self.update(|b| b.bounce_jitter(jitter));
+    }
+
+    /// Finish the game after given game time (milliseconds).
+    pub fn time_limit(&mut self, time_limit: u64) {
+        self.update(|b| b.time_limit(time_limit));
+    }
+
+    /// Finish the game once a team owns given fraction of the board (e.g. `0.75`).
+    pub fn territory_threshold(&mut self, threshold: f32) {
+        self.update(|b| b.territory_threshold(threshold));
+    }
+
+    /// Finish the game once a team flips given number of cells.
+    pub fn flips_to_win(&mut self, flips: u32) {
+        self.update(|b| b.flips_to_win(flips));
+    }
+
+    /// Set the number of past states kept to rewind the game (one per tick).
+    pub fn rewind_capacity(&mut self, capacity: usize) {
+        self.update(|b| b.rewind_capacity(capacity));
+    }
+
+    /// Set the way the balls interact with each other.
+    pub fn ball_collisions(&mut self, mode: BallCollisionsMode) {
+        let collisions = match mode {
+            BallCollisionsMode::Off => BallCollisions::Off,
+            BallCollisionsMode::Bounce => BallCollisions::Bounce,
+            BallCollisionsMode::SameTeamOnly => BallCollisions::SameTeamOnly,
+        };
+        self.update(|b| b.ball_collisions(collisions));
+    }
+
+    /// Set the way the balls interact with the edges of the viewport.
+    pub fn boundaries(&mut self, mode: BoundariesMode) {
+        let boundaries = match mode {
+            BoundariesMode::Reflect => Boundaries::Reflect,
+            BoundariesMode::Wrap => Boundaries::Wrap,
+            BoundariesMode::Absorb => Boundaries::Absorb,
+        };
+        self.update(|b| b.boundaries(boundaries));
+    }
+
+    /// Set the width of the paddles (in cells).
+    pub fn paddle_width(&mut self, width: f32) {
+        self.update(|b| b.paddle_width(width));
+    }
+
+    /// Set the speed the paddles follow their targets with (pixels per millisecond).
+    pub fn paddle_speed(&mut self, speed: f32) {
+        self.update(|b| b.paddle_speed(speed));
+    }
+
+    /// Set the rebound angle off the end of a paddle (degrees from the vertical).
+    pub fn paddle_max_angle(&mut self, angle: f32) {
+        self.update(|b| b.paddle_max_angle(angle));
+    }
+}
+
+impl WasmGameConfig {
+    fn update(&mut self, f: impl FnOnce(GameConfigBuilder) -> GameConfigBuilder) {
+        self.builder = f(self.builder.clone());
+    }
+}
+
+impl Default for WasmGameConfig {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 #[wasm_bindgen]
 /// Game object.
 pub struct WasmGame {
     game: Game,
+    recorder: Option<ReplayRecorder>,
 }
 
 #[wasm_bindgen]
 impl WasmGame {
-    /// Create a new game given viewport size (pixels), the board size and starting time.
-    pub fn new(board_size: u8, viewport_x: u32, viewport_y: u32, start_time_ms: u64) -> Self {
+    /// Compute board dimensions that give square-ish cells for given viewport (pixels).
+    ///
+    /// The shorter side of the viewport is covered by `cells` cells and
+    /// the longer side gets as many cells as needed to keep the aspect ratio
+    /// (see [`Game::board_size_for`]). The board passed to [`WasmGame::new`]
+    /// is always stretched over the viewport, so this is the way to get square cells.
+    pub fn board_size_for(viewport_x: u32, viewport_y: u32, cells: u16) -> BoardSize {
+        let viewport_size = Position {
+            x: viewport_x as _,
+            y: viewport_y as _,
+        };
+        let (width, height) = Game::board_size_for(&viewport_size, cells);
+        BoardSize { width, height }
+    }
+
+    /// Create a new game given viewport size (pixels), the board dimensions,
+    /// number of teams, starting time and gameplay configuration.
+    ///
+    /// Throws if the board, viewport or configuration are invalid.
+    pub fn new(
+        board_width: u16,
+        board_height: u16,
+        teams: u8,
+        viewport_x: u32,
+        viewport_y: u32,
+        start_time_ms: u64,
+        config: &WasmGameConfig,
+    ) -> Result<WasmGame, JsError> {
         // game init
-        let board = Board::new(board_size);
+        let board = Board::try_with_teams(board_width, board_height, teams)?;
         let viewport_size = Position {
             x: viewport_x as _,
             y: viewport_y as _,
         };
-        let game = Game::new(board, start_time_ms, viewport_size);
+        let config = config.builder.clone().try_build()?;
+        let game = Game::try_new(board, start_time_ms, viewport_size, config)?;
+
+        Ok(Self {
+            game,
+            recorder: None,
+        })
+    }
+
+    /// Create a new game on a board loaded from ASCII art (see [`Board::from_ascii`]).
+    ///
+    /// Throws if the board, viewport or configuration are invalid.
+    pub fn from_ascii(
+        board: &str,
+        viewport_x: u32,
+        viewport_y: u32,
+        start_time_ms: u64,
+        config: &WasmGameConfig,
+    ) -> Result<WasmGame, JsError> {
+        let board = Board::from_ascii(board)?;
+        let viewport_size = Position {
+            x: viewport_x as _,
+            y: viewport_y as _,
+        };
+        let config = config.builder.clone().try_build()?;
+        let game = Game::try_new(board, start_time_ms, viewport_size, config)?;
+
+        Ok(Self {
+            game,
+            recorder: None,
+        })
+    }
+
+    /// Resume a game from a snapshot created by [`WasmGame::snapshot`].
+    ///
+    /// The game clock continues from given current time.
+    /// Throws if the snapshot is damaged or not supported.
+    pub fn restore(snapshot: &[u8], current_time_ms: u64) -> Result<WasmGame, JsError> {
+        let mut game = Game::restore(snapshot)?;
+        game.rebase_time(current_time_ms);
+        Ok(Self {
+            game,
+            recorder: None,
+        })
+    }
+
+    /// Save the full game state into a binary snapshot.
+    pub fn snapshot(&self) -> Vec<u8> {
+        self.game.snapshot()
+    }
+
+    /// Add a new ball for given team id (`1` for lit, `0` for dark).
+    ///
+    /// The ball starts at given position (pixels), heading at given angle
+    /// (degrees, clockwise from right). Returns the index of the ball.
+    /// Throws if the team is not playing on the board.
+    pub fn add_ball(&mut self, team: u8, x: u32, y: u32, angle: f32) -> Result<usize, JsError> {
+        let position = Position {
+            x: x as _,
+            y: y as _,
+        };
+        self.input(Input::AddBall {
+            team: State::try_team(team)?,
+            position,
+            angle,
+        })?;
+        Ok(self.game.balls().len() - 1)
+    }
+
+    /// Remove the ball at given index.
+    ///
+    /// Returns `false` if there was no such ball.
+    pub fn remove_ball(&mut self, index: usize) -> bool {
+        index < self.game.balls().len() && self.input(Input::RemoveBall { index }).is_ok()
+    }
+
+    /// Give the team a paddle centered at given position (pixels).
+    ///
+    /// The paddle moves only horizontally, the existing paddle of the team is replaced.
+    /// Throws if the team is not playing on the board.
+    pub fn add_paddle(&mut self, team: u8, x: u32, y: u32) -> Result<(), JsError> {
+        let position = Position {
+            x: x as _,
+            y: y as _,
+        };
+        self.input(Input::AddPaddle {
+            team: State::try_team(team)?,
+            position,
+        })?;
+        Ok(())
+    }
+
+    /// Remove the paddle of given team.
+    ///
+    /// Returns `false` if the team had no paddle.
+    pub fn remove_paddle(&mut self, team: u8) -> bool {
+        State::try_team(team).is_ok_and(|team| {
+            self.game.paddle(team).is_some() && self.input(Input::RemovePaddle { team }).is_ok()
+        })
+    }
 
-        Self { game }
+    /// Steer the paddle of given team towards given X coordinate (pixels, e.g. the mouse).
+    ///
+    /// The paddle follows the target during the next ticks and never leaves the viewport.
+    /// Throws if the team has no paddle.
+    pub fn set_paddle_target(&mut self, team: u8, x: i32) -> Result<(), JsError> {
+        self.input(Input::SetPaddleTarget {
+            team: State::try_team(team)?,
+            x: x as _,
+        })?;
+        Ok(())
     }
 
     /// Recalculate objects positions and game physics.
-    pub fn tick(&mut self, time_ms: u64) {
+    ///
+    /// Throws if the time did not move forward.
+    pub fn tick(&mut self, time_ms: u64) -> Result<(), JsError> {
         let _timer = Timer::new("Game::tick");
-        self.game.tick(time_ms)
+        match &mut self.recorder {
+            Some(recorder) => recorder.tick(&mut self.game, time_ms)?,
+            None => self.game.try_tick(time_ms)?,
+        }
+        Ok(())
+    }
+
+    /// Game time consumed by the physics (milliseconds).
+    pub fn elapsed(&self) -> u64 {
+        self.game.elapsed()
+    }
+
+    /// Rewind the game to the latest state recorded at or before given game time.
+    ///
+    /// Throws if there is no such state recorded or the game is being recorded
+    /// (the replays can't be rewound).
+    pub fn rewind_to(&mut self, elapsed_ms: u64) -> Result<(), JsError> {
+        self.ensure_not_recording()?;
+        self.game.rewind_to(elapsed_ms)?;
+        Ok(())
+    }
+
+    /// Rewind the game by a single recorded state.
+    ///
+    /// Returns the game time of the restored state
+    /// or `undefined` if there is no earlier state.
+    /// Throws if the game is being recorded (the replays can't be rewound).
+    pub fn step_back(&mut self) -> Result<Option<u64>, JsError> {
+        self.ensure_not_recording()?;
+        Ok(self.game.step_back())
+    }
+
+    /// Start recording a replay of the game from its current state.
+    ///
+    /// Any replay recorded so far is discarded.
+    pub fn start_recording(&mut self) {
+        self.recorder = Some(ReplayRecorder::new(&self.game));
+    }
+
+    /// Stop recording and export the replay file.
+    ///
+    /// Returns `undefined` if the game was not being recorded.
+    pub fn stop_recording(&mut self) -> Option<Vec<u8>> {
+        self.recorder
+            .take()
+            .map(|recorder| recorder.finish().to_bytes())
+    }
+
+    /// Take all the events emitted since the last call.
+    ///
+    /// The events are queued until taken, so this should be called
+    /// regularly (e.g. every frame).
+    pub fn drain_events(&mut self) -> Vec<EventObject> {
+        self.game.drain_events().map(EventObject::from).collect()
+    }
+
+    /// Return `true` if the game is over.
+    pub fn is_finished(&self) -> bool {
+        self.game.status().is_finished()
+    }
+
+    /// Return the id of the winning team.
+    ///
+    /// Returns `undefined` if the game is still running or ended with a draw.
+    pub fn winner(&self) -> Option<u8> {
+        match self.game.status() {
+            GameStatus::Finished { winner } => winner.map(State::id),
+            GameStatus::Running => None,
+        }
+    }
+
+    /// Return the scores of all the teams, the leading team first.
+    pub fn scores(&self) -> Vec<ScoreObject> {
+        self.game
+            .scoreboard()
+            .into_iter()
+            .map(|score| ScoreObject {
+                team: score.team().id(),
+                cells: score.cells(),
+                flips: score.flips(),
+            })
+            .collect()
+    }
+
+    /// Take the cells changed since the last call.
+    ///
+    /// Every cell is identified by its index `row * board_width + col`.
+    /// All the cells are reported on the first call.
+    pub fn take_dirty(&mut self) -> Vec<u32> {
+        let width = self.game.board().width() as u32;
+        self.game
+            .take_dirty()
+            .into_iter()
+            .map(|(row, col)| row as u32 * width + col as u32)
+            .collect()
     }
 
     /// Export the board state.
-    pub fn board_state_ptr(&self) -> *const [[u64; 4]; 256] {
+    ///
+    /// The pointer refers to `board_height * board_state_stride` `u64` words
+    /// in the WASM memory. Row `r` starts at word `r * board_state_stride`
+    /// and column `c` of that row is the bit `c % 64` of the word `c / 64`.
+    pub fn board_state_ptr(&self) -> *const u64 {
         let _timer = Timer::new("Game::state");
-        self.game.board().raw_state()
+        self.game.board().raw_state().as_ptr()
+    }
+
+    /// Number of board columns.
+    pub fn board_width(&self) -> u16 {
+        self.game.board().width()
+    }
+
+    /// Number of board rows.
+    pub fn board_height(&self) -> u16 {
+        self.game.board().height()
+    }
+
+    /// Number of bit planes of the board state.
+    ///
+    /// The team id of a cell is stored in binary, bit `i` in the plane `i`.
+    pub fn board_planes(&self) -> usize {
+        self.game.board().raw_planes().len()
+    }
+
+    /// Export a single bit plane of the board state.
+    ///
+    /// The layout is the same as for [`WasmGame::board_state_ptr`],
+    /// which is the plane `0`. Throws if there is no such plane.
+    pub fn board_plane_ptr(&self, plane: usize) -> Result<*const u64, JsError> {
+        let planes = self.game.board().raw_planes();
+        let plane = planes
+            .get(plane)
+            .ok_or_else(|| JsError::new("The plane index is beyond number of planes."))?;
+        Ok(plane.as_ptr())
+    }
+
+    /// Export the bit plane of the wall cells.
+    ///
+    /// The layout is the same as for [`WasmGame::board_state_ptr`].
+    /// The wall cells are not owned by any team, their bits in the team planes are clear.
+    pub fn board_walls_ptr(&self) -> *const u64 {
+        self.game.board().raw_walls().as_ptr()
+    }
+
+    /// Number of `u64` words occupied by every row of the exported board state.
+    pub fn board_state_stride(&self) -> usize {
+        self.game.board().raw_state().stride()
     }
 
     /// Export game objects positions.
+    ///
+    /// The ball and paddle positions are interpolated between the physics steps.
+    /// With the wrapped edges the balls straddling an edge are listed
+    /// once for every side they show up at (see [`Game::ball_images`]).
     pub fn game_objects(&self) -> GameObjects {
-        let lit_ball = self.game.lit_ball();
-        let dark_ball = self.game.dark_ball();
+        let balls = self
+            .game
+            .interpolated_balls()
+            .flat_map(|(team, position)| {
+                self.game
+                    .ball_images(&position)
+                    .into_iter()
+                    .map(move |image| BallObject {
+                        x: image.x as _,
+                        y: image.y as _,
+                        team: team.id(),
+                    })
+            })
+            .collect();
+        let paddles = self
+            .game
+            .interpolated_paddles()
+            .map(|(team, position)| PaddleObject {
+                x: position.x as _,
+                y: position.y as _,
+                team: team.id(),
+            })
+            .collect();
         let ball_radius = self.game.ball_radius();
         let cell_size = self.game.cell_size();
+        let paddle_size = self.game.paddle_size();
 
         GameObjects {
-            lit_ball_x: lit_ball.x as _,
-            lit_ball_y: lit_ball.y as _,
-            dark_ball_x: dark_ball.x as _,
-            dark_ball_y: dark_ball.y as _,
+            balls,
+            paddles,
             cell_size_x: cell_size.x as _,
             cell_size_y: cell_size.y as _,
             ball_radius: ball_radius as _,
+            paddle_width: paddle_size.x as _,
+            paddle_height: paddle_size.y as _,
+        }
+    }
+}
+
+impl WasmGame {
+    /// Reject rewinding the game that is being recorded,
+    /// it would make the replay diverge from the game.
+    fn ensure_not_recording(&self) -> Result<(), RewindWhileRecording> {
+        match self.recorder {
+            Some(_) => Err(RewindWhileRecording),
+            None => Ok(()),
+        }
+    }
+
+    fn input(&mut self, input: Input) -> Result<(), Error> {
+        match &mut self.recorder {
+            Some(recorder) => recorder.input(&mut self.game, input),
+            None => input.apply(&mut self.game),
         }
     }
 }
 
+/// The game can't be rewound while it's being recorded
+/// (see [`engine_rs::replay::ReplayRecorder`]).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct RewindWhileRecording;
+
+impl fmt::Display for RewindWhileRecording {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "The game can't be rewound while it's being recorded.")
+    }
+}
+
+impl std::error::Error for RewindWhileRecording {}
+
 struct Timer<'a> {
     name: &'a str,
 }
@@ -99,3 +764,38 @@ impl<'a> Drop for Timer<'a> {
         web_sys::console::time_end_with_label(self.name);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_size_the_board_for_square_cells() {
+        let banner = WasmGame::board_size_for(960, 240, 8);
+        let portrait = WasmGame::board_size_for(360, 720, 12);
+
+        assert_eq!((banner.width, banner.height), (32, 8));
+        assert_eq!((portrait.width, portrait.height), (12, 24));
+    }
+
+    #[test]
+    fn should_not_rewind_while_recording() {
+        let mut config = WasmGameConfig::new();
+        config.rewind_capacity(10);
+        let Ok(mut game) = WasmGame::new(16, 16, 2, 320, 320, 0, &config) else {
+            panic!("The game should be created");
+        };
+
+        // when
+        game.start_recording();
+
+        // then
+        assert_eq!(game.ensure_not_recording(), Err(RewindWhileRecording));
+
+        // when
+        game.stop_recording();
+
+        // then
+        assert_eq!(game.ensure_not_recording(), Ok(()));
+    }
+}
@@ -1,31 +1,101 @@
-import { WasmGame } from "engine-rs-js";
+import { BallCollisionsMode, EventKind, WasmGame, WasmGameConfig } from "engine-rs-js";
 import { memory } from "engine-rs-js/engine_rs_js_bg";
 
-const size = 480;
+const width = 480;
+const height = 480;
 const time = () => BigInt(Date.now());
 
 const $canvas = document.getElementById('canvas');
-$canvas.setAttribute("width", size);
-$canvas.setAttribute("height", size);
+$canvas.setAttribute("width", width);
+$canvas.setAttribute("height", height);
 
 let previous_time = time() - BigInt(1);
-const board_size = 16;
-const game = WasmGame.new(board_size, size, size, previous_time);
-
-const cell_size = size / board_size;
-const LIT_BALL = "rgb(200, 200, 200)";
-const LIT_CELL = "rgb(50,50,50)";
-const DARK_BALL = "rgb(70, 70, 70)";
-const DARK_CELL = "rgb(180, 180, 180)";
+// square cells, 16 of them along the shorter side of the canvas.
+const board_size = WasmGame.board_size_for(width, height, 16);
+const teams = 2;
+const config = WasmGameConfig.new();
+// keep a few seconds of history to rewind.
+config.rewind_capacity(600);
+// don't let the balls pass through each other.
+config.ball_collisions(BallCollisionsMode.Bounce);
+
+// resume the previous session if the page was reloaded.
+const SNAPSHOT_KEY = "wasm-game-snapshot";
+const restoreGame = () => {
+  const saved = localStorage.getItem(SNAPSHOT_KEY);
+  if (!saved) {
+    return null;
+  }
+  try {
+    const bytes = Uint8Array.from(atob(saved), (c) => c.charCodeAt(0));
+    const restored = WasmGame.restore(bytes, previous_time);
+    if (restored.is_finished()) {
+      // a finished game is not worth resuming, start a new one.
+      localStorage.removeItem(SNAPSHOT_KEY);
+      return null;
+    }
+    return restored;
+  } catch (e) {
+    console.warn("Unable to restore the game", e);
+    return null;
+  }
+};
+const game = restoreGame()
+  ?? WasmGame.new(board_size.width, board_size.height, teams, width, height, previous_time, config);
+window.addEventListener("beforeunload", () => {
+  if (game.is_finished()) {
+    localStorage.removeItem(SNAPSHOT_KEY);
+    return;
+  }
+  const bytes = game.snapshot();
+  localStorage.setItem(SNAPSHOT_KEY, btoa(String.fromCharCode(...bytes)));
+});
+
+// the restored game keeps the board it was saved with.
+const board_width = game.board_width();
+const board_height = game.board_height();
+const cell_width = width / board_width;
+const cell_height = height / board_height;
+// colors indexed by the team id (0 - dark, 1 - lit)
+const BALL_COLORS = ["rgb(70, 70, 70)", "rgb(200, 200, 200)", "rgb(200, 120, 40)", "rgb(120, 60, 160)"];
+const CELL_COLORS = ["rgb(180, 180, 180)", "rgb(50,50,50)", "rgb(240, 200, 150)", "rgb(200, 170, 220)"];
+const WALL_COLOR = "rgb(120, 40, 40)";
+
+// recently flipped cells, highlighted for a short while.
+const FLASH_MS = 150;
+let flashes = [];
 
 const render = () => {
   const ctx = $canvas.getContext('2d');
   const gameObjects = game.game_objects();
 
   renderCells(ctx);
+  renderFlashes(ctx);
   renderBalls(ctx, gameObjects);
 };
 
+const collectEvents = () => {
+  const now = Date.now();
+  flashes = flashes.filter((flash) => now - flash.at < FLASH_MS);
+  for (const event of game.drain_events()) {
+    if (event.kind === EventKind.CellFlipped) {
+      flashes.push({ row: event.row, col: event.col, at: now });
+    }
+    if (event.kind === EventKind.GameFinished) {
+      const scores = game.scores().map((s) => `team ${s.team}: ${s.cells} cells, ${s.flips} flips`);
+      console.log(`Game over, winner: ${game.winner() ?? "draw"}`, scores);
+    }
+  }
+};
+
+const renderFlashes = (ctx) => {
+  const now = Date.now();
+  for (const flash of flashes) {
+    ctx.fillStyle = `rgba(255, 255, 255, ${1 - (now - flash.at) / FLASH_MS})`;
+    ctx.fillRect(flash.col * cell_width, flash.row * cell_height, cell_width, cell_height);
+  }
+};
+
 const renderBalls = (ctx, g) => {
   const drawBall = (x, y, radius) => {
     ctx.beginPath();
@@ -33,50 +103,90 @@ const renderBalls = (ctx, g) => {
     ctx.fill();
   };
 
-  ctx.fillStyle = LIT_BALL;
-  drawBall(g.lit_ball_x, g.lit_ball_y, g.ball_radius);
-
-  ctx.fillStyle = DARK_BALL;
-  drawBall(g.dark_ball_x, g.dark_ball_y, g.ball_radius);
+  for (const ball of g.balls) {
+    ctx.fillStyle = BALL_COLORS[ball.team];
+    drawBall(ball.x, ball.y, g.ball_radius);
+  }
 };
 
-const renderCells = (ctx) => {
-  const cellsPtr = game.board_state_ptr();
-  const cells = new BigUint64Array(memory.buffer, cellsPtr, 256 * 4);
+// the cells are drawn incrementally on a separate canvas,
+// only the ones that changed since the previous frame.
+const $cells = document.createElement('canvas');
+$cells.setAttribute("width", width);
+$cells.setAttribute("height", height);
 
-  const drawCell = (row, col) => {
-    ctx.fillRect(col * cell_size, row * cell_size, cell_size, cell_size);
-  };
-  ctx.fillStyle = LIT_CELL;
-  forEveryCell(cells, (row, col, isSet) => {
-    if (isSet) {
-      drawCell(row, col);
+const renderCells = (ctx) => {
+  const dirty = game.take_dirty();
+  if (dirty.length > 0) {
+    const stride = game.board_state_stride();
+    const planes = [];
+    for (let plane = 0; plane < game.board_planes(); ++plane) {
+      const planePtr = game.board_plane_ptr(plane);
+      planes.push(new BigUint64Array(memory.buffer, planePtr, board_height * stride));
     }
-  });
+    const walls = new BigUint64Array(memory.buffer, game.board_walls_ptr(), board_height * stride);
+
+    const cellsCtx = $cells.getContext('2d');
+    for (const index of dirty) {
+      const row = Math.floor(index / board_width);
+      const col = index % board_width;
+      cellsCtx.fillStyle = isSet(walls, stride, row, col)
+        ? WALL_COLOR
+        : CELL_COLORS[cellTeam(planes, stride, row, col)];
+      cellsCtx.fillRect(col * cell_width, row * cell_height, cell_width, cell_height);
+    }
+  }
+  ctx.drawImage($cells, 0, 0);
+};
 
-  ctx.fillStyle = DARK_CELL;
-  forEveryCell(cells, (row, col, isSet) => {
-    if (!isSet) {
-      drawCell(row, col);
+const isSet = (cells, stride, row, col) => {
+  const word = row * stride + Math.floor(col / 64);
+  const bit = BigInt(col % 64);
+  return ((cells[word] >> bit) & 0x1n) === 0x1n;
+};
+
+const cellTeam = (planes, stride, row, col) => {
+  let team = 0;
+  planes.forEach((cells, plane) => {
+    if (isSet(cells, stride, row, col)) {
+      team |= 1 << plane;
     }
   });
+  return team;
 };
 
-const forEveryCell = (cells, callback) => {
-  for (let row = 0; row < board_size; ++row) {
-    const row_parts = cells.slice(row * 4);
-    for (let part_idx = 0; part_idx < (board_size + 63) / 64; ++part_idx) {
-      let part = row_parts[part_idx];
-      const col_max = Math.min(board_size, (part_idx + 1) * 64);
-      for (let col = part_idx * 64; col < col_max; ++col) {
-        const isSet = 0x1n & part; 
-        part = part >> 1n;
-        
-        callback(row, col, isSet);
-      }
-    }
+// press the left arrow to rewind by a second (not while recording a replay).
+document.addEventListener("keydown", (e) => {
+  if (e.key !== "ArrowLeft" || recording) {
+    return;
   }
-};
+  const target = game.elapsed() - 1000n;
+  try {
+    game.rewind_to(target > 0n ? target : 0n);
+  } catch (e) {
+    // not recorded that far back, go to the oldest state.
+    while (game.step_back() !== undefined);
+  }
+});
+
+// press `R` to start recording a replay and again to download it.
+let recording = false;
+document.addEventListener("keydown", (e) => {
+  if (e.key !== "r") {
+    return;
+  }
+  recording = !recording;
+  if (recording) {
+    game.start_recording();
+    return;
+  }
+  const replay = game.stop_recording();
+  const $link = document.createElement("a");
+  $link.href = URL.createObjectURL(new Blob([replay]));
+  $link.download = "game.replay";
+  $link.click();
+  URL.revokeObjectURL($link.href);
+});
 
 const tick = () => {
   const current_time = time();
@@ -84,6 +194,7 @@ const tick = () => {
     game.tick(current_time);
     previous_time = current_time;
   }
+  collectEvents();
 };
 
 const tickAndRender = () => {
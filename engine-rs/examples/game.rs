@@ -1,4 +1,4 @@
-use engine_rs::board::State;
+use engine_rs::board::{State, MAX_TEAMS};
 use sdl2::event::Event;
 use sdl2::keyboard::Keycode;
 use sdl2::rect::{Point, Rect};
@@ -8,9 +8,29 @@ use std::time;
 
 use engine_rs::{
     board::Board,
+    config::GameConfig,
     game::{Game, Position},
 };
 
+/// Cell colors indexed by the team id.
+const CELL_COLORS: [Color; MAX_TEAMS as usize] = [
+    Color::RGB(152, 251, 152),
+    Color::RGB(135, 206, 250),
+    Color::RGB(250, 218, 135),
+    Color::RGB(221, 160, 221),
+];
+
+/// Color of the wall cells.
+const WALL_COLOR: Color = Color::RGB(105, 105, 105);
+
+/// Ball colors indexed by the team id.
+const BALL_COLORS: [Color; MAX_TEAMS as usize] = [
+    Color::RGB(80, 250, 60),
+    Color::RGB(175, 238, 238),
+    Color::RGB(250, 160, 60),
+    Color::RGB(186, 85, 211),
+];
+
 fn main() -> Result<(), String> {
     // sdl init
     let sdl_context = sdl2::init()?;
@@ -30,13 +50,33 @@ fn main() -> Result<(), String> {
     canvas.present();
 
     // game init
-    let board = Board::new(16);
+    let board = Board::square(16);
     let viewport_size = Position {
         x: size as f32,
         y: size as f32,
     };
     let start_time = time::Instant::now();
-    let mut game = Game::new(board, 0, viewport_size);
+    let mut game = Game::new(board, 0, viewport_size, GameConfig::default());
+    // the lit paddle at the bottom, the dark one at the top.
+    let paddle_height = game.paddle_size().y;
+    let paddles = [
+        (
+            State::LIT,
+            size as f32 - paddle_height,
+            Keycode::Left,
+            Keycode::Right,
+        ),
+        (State::DARK, paddle_height, Keycode::A, Keycode::D),
+    ];
+    for (team, y, ..) in paddles {
+        game.add_paddle(
+            team,
+            Position {
+                x: size as f32 / 2.0,
+                y,
+            },
+        );
+    }
 
     let mut event_pump = sdl_context.event_pump()?;
 
@@ -52,6 +92,30 @@ fn main() -> Result<(), String> {
                 } => {
                     break 'running;
                 }
+                // hold the keys to move the paddles towards the edges.
+                Event::KeyDown {
+                    keycode: Some(key),
+                    repeat: false,
+                    ..
+                } => {
+                    for (team, _, left, right) in paddles {
+                        if key == left {
+                            game.set_paddle_target(team, 0.0);
+                        } else if key == right {
+                            game.set_paddle_target(team, size as f32);
+                        }
+                    }
+                }
+                Event::KeyUp {
+                    keycode: Some(key), ..
+                } => {
+                    for (team, _, left, right) in paddles {
+                        if key == left || key == right {
+                            let x = game.paddle(team).map_or(0.0, |paddle| paddle.position().x);
+                            game.set_paddle_target(team, x);
+                        }
+                    }
+                }
                 _ => {}
             }
         }
@@ -72,37 +136,44 @@ fn render(
 ) -> Result<(), String> {
     canvas.clear();
 
-    let lit_color = Color::RGB(80, 250, 60);
-    let dark_color = Color::RGB(175, 238, 238);
-
     let board = game.board();
-    let board_size = board.size();
     let cell_size = game.cell_size();
-    for row in 0..board_size {
-        for col in 0..board_size {
-            let kind = board.cell(col, row);
-            canvas.set_draw_color(match kind {
-                State::Dark => Color::RGB(152, 251, 152),
-                State::Lit => Color::RGB(135, 206, 250),
+    for row in 0..board.height() {
+        for col in 0..board.width() {
+            let kind = board.cell(row, col);
+            canvas.set_draw_color(if kind.is_wall() {
+                WALL_COLOR
+            } else {
+                CELL_COLORS[kind.id() as usize]
             });
             canvas.fill_rect(Rect::new(
-                row as i32 * cell_size.x as i32,
-                col as i32 * cell_size.y as i32,
+                col as i32 * cell_size.x as i32,
+                row as i32 * cell_size.y as i32,
                 cell_size.x as u32,
                 cell_size.y as u32,
             ))?;
         }
     }
 
-    let ball_radius = ((cell_size.x + cell_size.y) / 4.0) as i32;
+    let ball_radius = game.ball_radius() as i32;
 
-    canvas.set_draw_color(dark_color);
-    let b = game.lit_ball();
-    draw_filled_circle(canvas, b.x as i32, b.y as i32, ball_radius)?;
+    for (team, b) in game.interpolated_balls() {
+        canvas.set_draw_color(BALL_COLORS[team.id() as usize]);
+        for image in game.ball_images(&b) {
+            draw_filled_circle(canvas, image.x as i32, image.y as i32, ball_radius)?;
+        }
+    }
 
-    canvas.set_draw_color(lit_color);
-    let b = game.dark_ball();
-    draw_filled_circle(canvas, b.x as i32, b.y as i32, ball_radius)?;
+    let paddle_size = game.paddle_size();
+    for (team, p) in game.interpolated_paddles() {
+        canvas.set_draw_color(BALL_COLORS[team.id() as usize]);
+        canvas.fill_rect(Rect::new(
+            (p.x - paddle_size.x / 2.0) as i32,
+            (p.y - paddle_size.y / 2.0) as i32,
+            paddle_size.x as u32,
+            paddle_size.y as u32,
+        ))?;
+    }
 
     canvas.present();
     Ok(())
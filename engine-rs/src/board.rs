@@ -2,101 +2,877 @@
 //!
 //! The game board is indepdent from the flying balls,
 //! it is only responsible for maintaing the board state,
-//! i.e. which cells are lit or dark.
+//! i.e. which team owns each of the cells.
 
 use std::fmt::{self, Write};
+use std::str::FromStr;
+
+use crate::error::{Error, OrPanic};
+use crate::layout::Layout;
+use crate::snapshot::{Encode, Reader, Writer};
 
 const ROW_PART_SIZE: usize = 64;
-type RawRow = [u64; 4];
-/// Raw state info.
-pub type RawState = [RawRow; 256];
+type RawRow = [u64];
 
 /// Row/Column indexing type.
-pub type Index = u8;
+pub type Index = u16;
 
-/// High level cell/ball state.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum State {
-    /// The cell/ball is lit.
-    Lit,
+/// Maximal number of teams a board can be split into.
+pub const MAX_TEAMS: u8 = 4;
+
+/// Raw state info.
+///
+/// A bit vector sized to the board dimensions. Every row
+/// occupies [`RawState::stride`] consecutive `u64` words
+/// (`ceil(width / 64)`), so the whole state takes `height * stride` words.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RawState {
+    stride: usize,
+    words: Vec<u64>,
+}
+
+impl RawState {
+    fn new(width: Index, height: Index) -> Self {
+        let stride = (width as usize).div_ceil(ROW_PART_SIZE);
+        Self {
+            stride,
+            words: vec![0; stride * height as usize],
+        }
+    }
+
+    /// Number of `u64` words occupied by a single row.
+    pub fn stride(&self) -> usize {
+        self.stride
+    }
+
+    /// View all the words of the state, row after row.
+    pub fn words(&self) -> &[u64] {
+        &self.words
+    }
+
+    /// Pointer to the first word of the state.
+    ///
+    /// Row `r` starts at word `r * stride`, column `c` of that row
+    /// is the bit `c % 64` of the word `c / 64`.
+    pub fn as_ptr(&self) -> *const u64 {
+        self.words.as_ptr()
+    }
+}
+
+/// High level cell/ball state, i.e. the team owning the cell or the ball.
+///
+/// The classic game is played by two teams: [`State::LIT`] and [`State::DARK`].
+/// Boards with more teams number the additional ones from `2` up to [`MAX_TEAMS`] (exclusive).
+/// Cells can also be [`State::WALL`]s, which are not owned by any team.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "u8", into = "u8")
+)]
+pub struct State(u8);
+
+impl State {
     /// The cell/ball is dark.
-    Dark,
+    pub const DARK: State = State(0);
+    /// The cell/ball is lit.
+    pub const LIT: State = State(1);
+    /// The cell is a wall (an obstacle), it can't be flipped by the balls.
+    pub const WALL: State = State(u8::MAX);
+
+    /// Return the state of a team with given id.
+    ///
+    /// Panics if the id is not lower than [`MAX_TEAMS`].
+    pub fn team(id: u8) -> Self {
+        Self::try_team(id).or_panic()
+    }
+
+    /// Return the state of a team with given id.
+    ///
+    /// Returns an error if the id is not lower than [`MAX_TEAMS`].
+    pub fn try_team(id: u8) -> Result<Self, Error> {
+        if id >= MAX_TEAMS {
+            return Err(Error::UnknownTeam(id));
+        }
+        Ok(Self(id))
+    }
+
+    /// Return the team id.
+    ///
+    /// The id matches the bits stored in the consecutive bit planes of the board.
+    /// The id of the [`State::WALL`] is `u8::MAX`.
+    pub fn id(self) -> u8 {
+        self.0
+    }
+
+    /// Return `true` if the cell is a wall.
+    pub fn is_wall(self) -> bool {
+        self == State::WALL
+    }
+
+    /// Iterate over the first `teams` states.
+    pub fn all(teams: u8) -> impl Iterator<Item = State> {
+        (0..teams).map(State::team)
+    }
+}
+
+/// Accepts the team ids and the id of the [`State::WALL`].
+impl TryFrom<u8> for State {
+    type Error = Error;
+
+    fn try_from(id: u8) -> Result<Self, Error> {
+        if id == Self::WALL.0 {
+            return Ok(Self::WALL);
+        }
+        Self::try_team(id)
+    }
+}
+
+impl From<State> for u8 {
+    fn from(state: State) -> u8 {
+        state.id()
+    }
+}
+
+impl fmt::Debug for State {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            State::DARK => f.write_str("Dark"),
+            State::LIT => f.write_str("Lit"),
+            State::WALL => f.write_str("Wall"),
+            State(id) => f.debug_tuple("Team").field(&id).finish(),
+        }
+    }
 }
 
 /// A representation of the game board.
 ///
-/// The game board is a square filled with cells that can be either
-/// "Lit" or "Dark".
+/// The game board is a rectangle filled with cells that are owned by
+/// one of the teams, by default either "Lit" or "Dark".
 ///
-/// Within the dimensions of the board there are two balls that move
+/// Within the dimensions of the board there are balls that move
 /// in a continous manner (independently from the cells).
-/// One ball is "Lit", second one "Dark".
+/// Every ball belongs to one of the teams.
+///
+/// When the ball hits a cell owned by another team,
+/// it bounces of it and converts the cell to its own team ("Lit" <> "Dark").
+/// The balls also bounce of the walls, which are never converted.
 ///
-/// When the ball hits a cell that is of the opposite kind,
-/// it bounces of it and flips the cell state ("Lit" <> "Dark").
+/// The board keeps track of the cells changed since the last [`Board::take_dirty`],
+/// so that the renderers can redraw only what has changed.
+#[derive(Clone)]
 pub struct Board {
-    /// Size of the board - both width and height (number of cells).
-    size: Index,
+    /// Width of the board (number of cells in a row).
+    width: Index,
+
+    /// Height of the board (number of rows).
+    height: Index,
 
-    /// A bit vector representing every cell on the board.
+    /// Number of teams the cells are split into.
+    teams: u8,
+
+    /// Bit planes representing every cell on the board.
     ///
-    /// The cell state can be either "Lit" or "Dark" represented by `1` and `0`
+    /// The team id of a cell is stored in binary, one bit per plane
+    /// (the least significant bit in the first plane).
+    /// In particular, for two teams there is a single plane and the cell
+    /// state can be either "Lit" or "Dark" represented by `1` and `0`
     /// correspondigly.
     ///
     /// The cells in a single row are represented by consecutive bits (left to right),
     /// rows are concatenated from top to bottom into the state.
-    state: RawState,
+    planes: Vec<RawState>,
+
+    /// Bit plane of the wall cells.
+    ///
+    /// The wall cells are not owned by any team, i.e. their bits are
+    /// clear in all the other planes.
+    walls: RawState,
+
+    /// Number of cells owned by every team.
+    ///
+    /// The counts are updated along with every cell change.
+    counts: [u32; MAX_TEAMS as usize],
+
+    /// Number of wall cells, updated along with the counts.
+    wall_count: u32,
+
+    /// Cells changed since the dirty cells were last taken.
+    ///
+    /// The layout is the same as of the bit planes.
+    dirty: RawState,
+}
+
+impl PartialEq for Board {
+    fn eq(&self, other: &Self) -> bool {
+        // the dirty cells are rendering bookkeeping, not the state of the board.
+        self.width == other.width
+            && self.height == other.height
+            && self.teams == other.teams
+            && self.planes == other.planes
+            && self.walls == other.walls
+    }
 }
 
+impl Eq for Board {}
+
 impl fmt::Debug for Board {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        StateOps::debug(f, self.size, &self.state)?;
-        f.debug_struct("Board").field("size", &self.size).finish()
+        StateOps::debug(f, self.width, self.height, &self.planes, &self.walls)?;
+        f.debug_struct("Board")
+            .field("width", &self.width)
+            .field("height", &self.height)
+            .field("teams", &self.teams)
+            .finish()
+    }
+}
+
+impl FromStr for Board {
+    type Err = Error;
+
+    /// Parse the board, see [`Board::from_ascii`].
+    fn from_str(ascii: &str) -> Result<Self, Self::Err> {
+        Self::from_ascii(ascii)
     }
 }
 
 impl Board {
-    /// Create and initialize a new board of given size.
-    pub fn new(size: Index) -> Self {
-        assert!(size > 1, "The board is too small");
-        let state = StateOps::initial(size);
+    /// Create and initialize a new board of given width and height.
+    ///
+    /// The board is split between the "Lit" and "Dark" teams.
+    /// Panics if the board is too small (see [`Board::try_new`]).
+    pub fn new(width: Index, height: Index) -> Self {
+        Self::try_new(width, height).or_panic()
+    }
+
+    /// Create and initialize a new board of given width and height.
+    ///
+    /// Returns an error if the board is smaller than 2x2.
+    pub fn try_new(width: Index, height: Index) -> Result<Self, Error> {
+        Self::try_with_teams(width, height, 2)
+    }
+
+    /// Create and initialize a new square board of given size.
+    pub fn square(size: Index) -> Self {
+        Self::new(size, size)
+    }
+
+    /// Create and initialize a new board split into given number of teams.
+    ///
+    /// Panics if the board is too small or the number of teams is not supported
+    /// (see [`Board::try_with_teams`]).
+    pub fn with_teams(width: Index, height: Index, teams: u8) -> Self {
+        Self::try_with_teams(width, height, teams).or_panic()
+    }
+
+    /// Create and initialize a new board split into given number of teams.
+    ///
+    /// The board is divided into equal stripes across its longer side,
+    /// see [`Layout::Stripes`].
+    pub fn try_with_teams(width: Index, height: Index, teams: u8) -> Result<Self, Error> {
+        Self::try_with_layout(width, height, teams, &Layout::Stripes)
+    }
+
+    /// Create a new board split between given number of teams according to the layout.
+    ///
+    /// Panics if the board is too small or the number of teams is not supported
+    /// (see [`Board::try_with_layout`]).
+    pub fn with_layout(width: Index, height: Index, teams: u8, layout: &Layout) -> Self {
+        Self::try_with_layout(width, height, teams, layout).or_panic()
+    }
 
-        Self { size, state }
+    /// Create a new board split between given number of teams according to the layout.
+    pub fn try_with_layout(
+        width: Index,
+        height: Index,
+        teams: u8,
+        layout: &Layout,
+    ) -> Result<Self, Error> {
+        Self::check_dimensions(width, height, teams)?;
+        let planes = StateOps::initial(width, height, teams, layout);
+        let walls = RawState::new(width, height);
+        Ok(Self::from_planes(width, height, teams, planes, walls))
     }
 
-    /// Return the size of the board.
-    pub fn size(&self) -> Index {
-        self.size
+    /// Load the board from ASCII art.
+    ///
+    /// Every line of the text is a row of the board, every character is a cell,
+    /// using the same glyphs as the [`fmt::Debug`] output (`▣`, `▢`, `▤`, `▥`, `█`)
+    /// or plain `#` for the "Lit", `.` for the "Dark" and `X` for the wall cells.
+    /// Blank lines and the whitespace around the rows are ignored.
+    /// The board is played by two teams, unless there are cells of other teams.
+    ///
+    /// The rows may be followed by the summary line of the [`fmt::Debug`] output
+    /// (e.g. `Board { width: 5, height: 2, teams: 3 }`, or `Board { size: 5 }`
+    /// for square boards), so the rendered boards can be parsed back.
+    /// The dimensions in the summary have to match the rows, the number
+    /// of teams overrides the one inferred from the cells.
+    ///
+    /// Returns an error if the rows have different lengths, there are
+    /// unknown characters or the summary does not match the cells.
+    pub fn from_ascii(ascii: &str) -> Result<Self, Error> {
+        let mut lines: Vec<&str> = ascii
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .collect();
+        let summary = match lines.last() {
+            Some(line) if line.starts_with("Board") => {
+                let summary = Summary::parse(line)?;
+                lines.pop();
+                Some(summary)
+            }
+            _ => None,
+        };
+        let rows: Vec<Vec<State>> = lines
+            .into_iter()
+            .map(|line| line.chars().map(StateOps::parse_glyph).collect())
+            .collect::<Result<_, _>>()?;
+        let height = rows.len();
+        let width = rows.first().map_or(0, Vec::len);
+        if rows.iter().any(|row| row.len() != width) {
+            return Err(Error::InvalidCells("Rows of different lengths"));
+        }
+        let max_team = rows
+            .iter()
+            .flatten()
+            .filter(|state| !state.is_wall())
+            .map(|state| state.0)
+            .max();
+        let teams = match summary.as_ref().and_then(|summary| summary.teams) {
+            Some(teams) => teams,
+            None => max_team.map_or(2, |team| (team + 1).max(2)),
+        };
+        let (width, height) = (
+            Index::try_from(width).map_err(|_| Error::InvalidCells("Too many columns"))?,
+            Index::try_from(height).map_err(|_| Error::InvalidCells("Too many rows"))?,
+        );
+        if let Some(summary) = summary {
+            if summary.width.is_some_and(|w| w != width)
+                || summary.height.is_some_and(|h| h != height)
+            {
+                return Err(Error::InvalidCells("Dimensions do not match the cells"));
+            }
+        }
+        Self::check_dimensions(width, height, teams)?;
+        if let Some(team) = max_team.filter(|team| *team >= teams) {
+            return Err(Error::UnknownTeam(team));
+        }
+
+        let mut planes = vec![RawState::new(width, height); StateOps::planes_count(teams)];
+        let mut walls = RawState::new(width, height);
+        for (row_index, row) in rows.iter().enumerate() {
+            for (col_index, state) in row.iter().enumerate() {
+                let (row_index, col_index) = (row_index as Index, col_index as Index);
+                if state.is_wall() {
+                    StateOps::mark(&mut walls, row_index, col_index);
+                } else {
+                    StateOps::set(&mut planes, row_index, col_index, *state);
+                }
+            }
+        }
+        Ok(Self::from_planes(width, height, teams, planes, walls))
+    }
+
+    fn from_planes(
+        width: Index,
+        height: Index,
+        teams: u8,
+        planes: Vec<RawState>,
+        walls: RawState,
+    ) -> Self {
+        let counts = StateOps::counts(width, height, &planes, &walls);
+        let wall_count = StateOps::count_marked(&walls);
+        Self {
+            width,
+            height,
+            teams,
+            planes,
+            walls,
+            counts,
+            wall_count,
+            dirty: StateOps::all_dirty(width, height),
+        }
+    }
+
+    /// Return the width of the board (number of columns).
+    pub fn width(&self) -> Index {
+        self.width
+    }
+
+    /// Return the height of the board (number of rows).
+    pub fn height(&self) -> Index {
+        self.height
+    }
+
+    /// Return the number of teams on the board.
+    pub fn teams(&self) -> u8 {
+        self.teams
+    }
+
+    /// Return the number of cells owned by given team.
+    ///
+    /// For the [`State::WALL`] it's the number of wall cells.
+    pub fn count(&self, team: State) -> u32 {
+        if team.is_wall() {
+            return self.wall_count;
+        }
+        self.counts[team.0 as usize]
+    }
+
+    /// Return the number of cells owned by every team on the board (indexed by team id).
+    pub fn counts(&self) -> &[u32] {
+        &self.counts[..self.teams as usize]
+    }
+
+    /// Return the fraction of the board owned by given team.
+    ///
+    /// The wall cells are not taken into account, so a board made of walls only
+    /// has no territory at all.
+    pub fn territory(&self, team: State) -> f32 {
+        let cells = self.width as u32 * self.height as u32 - self.wall_count;
+        if cells == 0 {
+            return 0.0;
+        }
+        self.count(team) as f32 / cells as f32
+    }
+
+    /// Take the cells changed since the last call (row and column indices).
+    ///
+    /// The cells are listed row after row. A freshly created board
+    /// reports all of its cells, so that it can be drawn for the first time.
+    /// Note a cell changed back and forth is still reported.
+    pub fn take_dirty(&mut self) -> Vec<(Index, Index)> {
+        let stride = self.dirty.stride;
+        let mut cells = Vec::new();
+        for (word_index, word) in self.dirty.words.iter_mut().enumerate() {
+            let mut bits = std::mem::take(word);
+            while bits != 0 {
+                let bit_index = bits.trailing_zeros() as usize;
+                bits &= bits - 1;
+                let row_index = (word_index / stride) as Index;
+                let col_index = ((word_index % stride) * ROW_PART_SIZE + bit_index) as Index;
+                cells.push((row_index, col_index));
+            }
+        }
+        cells
+    }
+
+    /// Return `true` if any cell changed since the dirty cells were last taken.
+    pub fn is_dirty(&self) -> bool {
+        self.dirty.words.iter().any(|word| *word != 0)
+    }
+
+    /// Copy the cells of the board, leaving out the dirty cells.
+    ///
+    /// The copy is meant only to [`Board::restore_from`] it later,
+    /// changing its cells would panic.
+    pub(crate) fn copy_cells(&self) -> Board {
+        Self {
+            planes: self.planes.clone(),
+            walls: self.walls.clone(),
+            dirty: RawState {
+                stride: self.dirty.stride,
+                words: Vec::new(),
+            },
+            ..*self
+        }
+    }
+
+    /// Overwrite the cells with the cells of the other board of the same size,
+    /// marking all the differing cells as dirty.
+    pub(crate) fn restore_from(&mut self, other: &Board) {
+        debug_assert_eq!((self.width, self.height), (other.width, other.height));
+        let planes = self.planes.iter_mut().zip(&other.planes);
+        for (plane, other) in planes.chain([(&mut self.walls, &other.walls)]) {
+            for ((word, other), dirty) in plane
+                .words
+                .iter_mut()
+                .zip(&other.words)
+                .zip(&mut self.dirty.words)
+            {
+                *dirty |= *word ^ *other;
+                *word = *other;
+            }
+        }
+        self.counts = other.counts;
+        self.wall_count = other.wall_count;
     }
 
     /// Get the raw state of the board.
     ///
-    /// Note the raw state may be bigger than the actual `board_size`.
+    /// This is the first bit plane, which for a two-team board
+    /// fully describes the state (see [`Board::raw_planes`]).
+    /// Note the rows of the raw state are padded to full `u64` words.
     pub fn raw_state(&self) -> &RawState {
-        &self.state
+        &self.planes[0]
+    }
+
+    /// Get all the bit planes of the board.
+    ///
+    /// The plane `i` holds the bit `i` of the team id of every cell.
+    pub fn raw_planes(&self) -> &[RawState] {
+        &self.planes
+    }
+
+    /// Get the bit plane of the wall cells.
+    ///
+    /// The layout is the same as of the team planes (see [`Board::raw_state`]).
+    pub fn raw_walls(&self) -> &RawState {
+        &self.walls
     }
 
     /// Inspect a single raw of the game board.
-    pub fn row(&self, row_index: Index) -> Row {
-        assert!(row_index < self.size, "The row index is beyond board size.");
-        let row = StateOps::row(&self.state, row_index);
-        Row { board: self, row }
+    ///
+    /// Panics if the row index is beyond board size.
+    pub fn row(&self, row_index: Index) -> Row<'_> {
+        self.try_row(row_index).or_panic()
+    }
+
+    /// Inspect a single raw of the game board.
+    pub fn try_row(&self, row_index: Index) -> Result<Row<'_>, Error> {
+        if row_index >= self.height {
+            return Err(Error::RowOutOfBounds(row_index));
+        }
+        Ok(Row {
+            board: self,
+            row_index,
+        })
     }
 
     /// Inspect a single cell state at given row and column index.
+    ///
+    /// Panics if the indices are beyond board size (see [`Board::get`]).
     pub fn cell(&self, row_index: Index, col_index: Index) -> State {
-        self.row(row_index).cell(col_index)
+        self.get(row_index, col_index).or_panic()
+    }
+
+    /// Inspect a single cell state at given row and column index.
+    pub fn get(&self, row_index: Index, col_index: Index) -> Result<State, Error> {
+        self.try_row(row_index)?.get(col_index)
+    }
+
+    /// Convert the cell at given row and column index to given team.
+    ///
+    /// Use [`State::WALL`] to place a wall, or a team to remove it.
+    /// Panics if the indices are beyond board size or the team is not playing on the board.
+    pub fn set_cell(&mut self, row_index: Index, col_index: Index, state: State) {
+        self.try_set_cell(row_index, col_index, state).or_panic()
+    }
+
+    /// Convert the cell at given row and column index to given team.
+    pub fn try_set_cell(
+        &mut self,
+        row_index: Index,
+        col_index: Index,
+        state: State,
+    ) -> Result<(), Error> {
+        self.check_bounds(row_index, col_index)?;
+        if !state.is_wall() && state.0 >= self.teams {
+            return Err(Error::UnknownTeam(state.0));
+        }
+        self.replace(row_index, col_index, state);
+        Ok(())
     }
 
     /// Flip the cell state at given row and column index.
+    ///
+    /// "Lit" cells become "Dark" and all the others become "Lit".
+    /// The walls are left intact.
+    /// Panics if the indices are beyond board size.
     pub fn flip(&mut self, row_index: Index, col_index: Index) {
-        assert!(row_index < self.size, "The row index is beyond board size.");
-        assert!(
-            col_index < self.size,
-            "The column index is beyond board size."
-        );
-        let row = StateOps::row_mut(&mut self.state, row_index);
-        StateOps::flip(row, col_index);
+        self.try_flip(row_index, col_index).or_panic()
+    }
+
+    /// Flip the cell state at given row and column index.
+    pub fn try_flip(&mut self, row_index: Index, col_index: Index) -> Result<(), Error> {
+        self.check_bounds(row_index, col_index)?;
+        let state = match StateOps::state(&self.planes, &self.walls, row_index, col_index) {
+            State::WALL => return Ok(()),
+            State::LIT => State::DARK,
+            _ => State::LIT,
+        };
+        self.replace(row_index, col_index, state);
+        Ok(())
+    }
+
+    /// Change the cell state and update the counts.
+    fn replace(&mut self, row_index: Index, col_index: Index, state: State) {
+        let previous = StateOps::state(&self.planes, &self.walls, row_index, col_index);
+        if previous == state {
+            return;
+        }
+        if previous.is_wall() {
+            StateOps::clear(&mut self.walls, row_index, col_index);
+            self.wall_count -= 1;
+        } else {
+            self.counts[previous.0 as usize] -= 1;
+        }
+        if state.is_wall() {
+            StateOps::set(&mut self.planes, row_index, col_index, State::DARK);
+            StateOps::mark(&mut self.walls, row_index, col_index);
+            self.wall_count += 1;
+        } else {
+            StateOps::set(&mut self.planes, row_index, col_index, state);
+            self.counts[state.0 as usize] += 1;
+        }
+        StateOps::mark(&mut self.dirty, row_index, col_index);
+    }
+
+    fn check_dimensions(width: Index, height: Index, teams: u8) -> Result<(), Error> {
+        if width < 2 || height < 2 {
+            return Err(Error::BoardTooSmall { width, height });
+        }
+        if !(2..=MAX_TEAMS).contains(&teams) {
+            return Err(Error::UnsupportedTeams(teams));
+        }
+        Ok(())
+    }
+
+    fn check_bounds(&self, row_index: Index, col_index: Index) -> Result<(), Error> {
+        if row_index >= self.height {
+            return Err(Error::RowOutOfBounds(row_index));
+        }
+        if col_index >= self.width {
+            return Err(Error::ColumnOutOfBounds(col_index));
+        }
+        Ok(())
+    }
+}
+
+impl Encode for State {
+    fn encode(&self, writer: &mut Writer) {
+        self.0.encode(writer);
+    }
+
+    fn decode(reader: &mut Reader) -> Result<Self, Error> {
+        Self::try_team(u8::decode(reader)?)
+    }
+}
+
+impl Encode for Board {
+    fn encode(&self, writer: &mut Writer) {
+        self.width.encode(writer);
+        self.height.encode(writer);
+        self.teams.encode(writer);
+        for plane in self.planes.iter().chain([&self.walls]) {
+            for word in &plane.words {
+                word.encode(writer);
+            }
+        }
+    }
+
+    fn decode(reader: &mut Reader) -> Result<Self, Error> {
+        let width = Index::decode(reader)?;
+        let height = Index::decode(reader)?;
+        let teams = u8::decode(reader)?;
+        Self::check_dimensions(width, height, teams)?;
+
+        let mut planes = vec![RawState::new(width, height); StateOps::planes_count(teams)];
+        let mut walls = RawState::new(width, height);
+        for plane in planes.iter_mut().chain([&mut walls]) {
+            for word in &mut plane.words {
+                *word = u64::decode(reader)?;
+            }
+            // the bits beyond the board width have to stay clear.
+            let padding = plane.stride * ROW_PART_SIZE - width as usize;
+            let tail = plane.stride - 1;
+            if padding > 0 {
+                let mask = !(u64::MAX >> padding);
+                if plane
+                    .words
+                    .iter()
+                    .skip(tail)
+                    .step_by(plane.stride)
+                    .any(|w| w & mask > 0)
+                {
+                    return Err(Error::CorruptedSnapshot("Cells beyond the board width"));
+                }
+            }
+        }
+        let owned_walls = planes.iter().any(|plane| {
+            (plane.words.iter())
+                .zip(&walls.words)
+                .any(|(word, walls)| word & walls > 0)
+        });
+        if owned_walls {
+            return Err(Error::CorruptedSnapshot("Walls owned by a team"));
+        }
+        let counts = StateOps::counts(width, height, &planes, &walls);
+        if counts[teams as usize..].iter().any(|count| *count > 0) {
+            return Err(Error::CorruptedSnapshot("Cells of unknown teams"));
+        }
+
+        let wall_count = StateOps::count_marked(&walls);
+
+        Ok(Self {
+            width,
+            height,
+            teams,
+            planes,
+            walls,
+            counts,
+            wall_count,
+            dirty: StateOps::all_dirty(width, height),
+        })
+    }
+}
+
+/// Compact human-readable representation of the board.
+///
+/// The cells are stored row after row as runs of the same team,
+/// every run is the number of cells followed by the team id
+/// as a letter (`a` for [`State::DARK`], `b` for [`State::LIT`], `c`, `d`
+/// and `x` for [`State::WALL`]),
+/// e.g. `8b8a8b8a` for the first two rows of the classic 16x16 board.
+#[cfg(feature = "serde")]
+mod serialize {
+    use std::fmt::Write;
+
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    use super::*;
+
+    #[derive(Serialize, Deserialize)]
+    struct BoardRepr {
+        width: Index,
+        height: Index,
+        teams: u8,
+        cells: String,
+    }
+
+    impl Serialize for Board {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            let mut cells = String::new();
+            let mut run: Option<(State, usize)> = None;
+            for row_index in 0..self.height {
+                for col_index in 0..self.width {
+                    let state = StateOps::state(&self.planes, &self.walls, row_index, col_index);
+                    run = match run {
+                        Some((team, count)) if team == state => Some((team, count + 1)),
+                        Some((team, count)) => {
+                            write_run(&mut cells, team, count);
+                            Some((state, 1))
+                        }
+                        None => Some((state, 1)),
+                    };
+                }
+            }
+            if let Some((team, count)) = run {
+                write_run(&mut cells, team, count);
+            }
+
+            BoardRepr {
+                width: self.width,
+                height: self.height,
+                teams: self.teams,
+                cells,
+            }
+            .serialize(serializer)
+        }
+    }
+
+    impl<'de> Deserialize<'de> for Board {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            let repr = BoardRepr::deserialize(deserializer)?;
+            from_repr(repr).map_err(serde::de::Error::custom)
+        }
+    }
+
+    fn write_run(cells: &mut String, team: State, count: usize) {
+        let letter = match team {
+            State::WALL => 'x',
+            team => (b'a' + team.0) as char,
+        };
+        write!(cells, "{count}{letter}").expect("writing to string never fails");
+    }
+
+    fn from_repr(repr: BoardRepr) -> Result<Board, Error> {
+        let BoardRepr {
+            width,
+            height,
+            teams,
+            cells,
+        } = repr;
+        let mut board = Board::try_with_teams(width, height, teams)?;
+        let total = width as usize * height as usize;
+        let mut index = 0;
+        let mut count: usize = 0;
+        for c in cells.chars() {
+            if let Some(digit) = c.to_digit(10) {
+                count = count
+                    .checked_mul(10)
+                    .and_then(|count| count.checked_add(digit as usize))
+                    .ok_or(Error::InvalidCells(
+                        "Run length does not match the board size",
+                    ))?;
+                continue;
+            }
+            let team = match c {
+                'a'..='d' => State(c as u8 - b'a'),
+                'x' => State::WALL,
+                _ => return Err(Error::InvalidCells("Unexpected character")),
+            };
+            if !team.is_wall() && team.0 >= teams {
+                return Err(Error::UnknownTeam(team.0));
+            }
+            if count == 0 || index + count > total {
+                return Err(Error::InvalidCells(
+                    "Run length does not match the board size",
+                ));
+            }
+            for cell in index..index + count {
+                let row_index = (cell / width as usize) as Index;
+                let col_index = (cell % width as usize) as Index;
+                board.replace(row_index, col_index, team);
+            }
+            index += count;
+            count = 0;
+        }
+        if index != total || count != 0 {
+            return Err(Error::InvalidCells(
+                "Run length does not match the board size",
+            ));
+        }
+        Ok(board)
+    }
+}
+
+/// The summary line of the [`fmt::Debug`] output of the board.
+#[derive(Debug, Default)]
+struct Summary {
+    width: Option<Index>,
+    height: Option<Index>,
+    teams: Option<u8>,
+}
+
+impl Summary {
+    /// Parse a line like `Board { width: 5, height: 2, teams: 3 }`.
+    fn parse(line: &str) -> Result<Self, Error> {
+        const INVALID: Error = Error::InvalidCells("Invalid board summary");
+        let fields = line
+            .strip_prefix("Board")
+            .map(str::trim)
+            .and_then(|rest| rest.strip_prefix('{'))
+            .and_then(|rest| rest.strip_suffix('}'))
+            .ok_or(INVALID)?;
+        let mut summary = Summary::default();
+        for field in fields.split(',').map(str::trim).filter(|f| !f.is_empty()) {
+            let (name, value) = field.split_once(':').ok_or(INVALID)?;
+            let value = value.trim();
+            match name.trim() {
+                "width" => summary.width = Some(value.parse().map_err(|_| INVALID)?),
+                "height" => summary.height = Some(value.parse().map_err(|_| INVALID)?),
+                "size" => {
+                    let size = value.parse().map_err(|_| INVALID)?;
+                    summary.width = Some(size);
+                    summary.height = Some(size);
+                }
+                "teams" => summary.teams = Some(value.parse().map_err(|_| INVALID)?),
+                _ => return Err(INVALID),
+            }
+        }
+        Ok(summary)
     }
 }
 
@@ -104,96 +880,189 @@ impl Board {
 #[derive(Debug)]
 pub struct Row<'a> {
     board: &'a Board,
-    row: &'a RawRow,
+    row_index: Index,
 }
 
 impl<'a> Row<'a> {
     /// Inspect the state of a single cell within the row.
     ///
-    /// Panics in case the column index is greater than the size of the game board.
+    /// Panics in case the column index is greater than the width of the game board.
     pub fn cell(&self, col_index: Index) -> State {
-        assert!(
-            col_index < self.board.size,
-            "The column index is beyond board size."
-        );
-        StateOps::cell(self.row, col_index)
+        self.get(col_index).or_panic()
+    }
+
+    /// Inspect the state of a single cell within the row.
+    pub fn get(&self, col_index: Index) -> Result<State, Error> {
+        if col_index >= self.board.width {
+            return Err(Error::ColumnOutOfBounds(col_index));
+        }
+        Ok(StateOps::state(
+            &self.board.planes,
+            &self.board.walls,
+            self.row_index,
+            col_index,
+        ))
     }
 }
 
 struct StateOps;
 
 impl StateOps {
-    pub fn initial(size: Index) -> RawState {
-        let mut state = [[0u64; 4]; 256];
-        let is_size_odd = size % 2 > 0;
-        for row_index in 0..size {
-            let row = &mut state[row_index as usize];
-            let max_cell_to_lit = if is_size_odd && row_index % 2 > 0 {
-                size / 2 + 1
-            } else {
-                size / 2
-            };
-            for col_index in 0..max_cell_to_lit {
-                let (part_index, bit_index) = Self::part_and_bit_index(col_index);
-                let cell = 1 << bit_index;
-                row[part_index] += cell;
+    /// Split the board between the teams according to the layout.
+    pub fn initial(width: Index, height: Index, teams: u8, layout: &Layout) -> Vec<RawState> {
+        let planes_count = Self::planes_count(teams);
+        let mut planes = vec![RawState::new(width, height); planes_count];
+        layout.fill(width, height, teams, |row_index, col_index, state| {
+            Self::set(&mut planes, row_index, col_index, state)
+        });
+        planes
+    }
+
+    /// Mark all the cells as dirty.
+    fn all_dirty(width: Index, height: Index) -> RawState {
+        let mut dirty = RawState::new(width, height);
+        for row_index in 0..height {
+            let row = Self::row_mut(&mut dirty, row_index);
+            for (part_index, part) in row.iter_mut().enumerate() {
+                let cells = (width as usize - part_index * ROW_PART_SIZE).min(ROW_PART_SIZE);
+                *part = u64::MAX >> (ROW_PART_SIZE - cells);
+            }
+        }
+        dirty
+    }
+
+    /// Count the cells owned by every team.
+    fn counts(
+        width: Index,
+        height: Index,
+        planes: &[RawState],
+        walls: &RawState,
+    ) -> [u32; MAX_TEAMS as usize] {
+        let mut counts = [0; MAX_TEAMS as usize];
+        for row_index in 0..height {
+            for col_index in 0..width {
+                if !Self::is_marked(walls, row_index, col_index) {
+                    counts[Self::cell(planes, row_index, col_index).0 as usize] += 1;
+                }
             }
         }
-        state
+        counts
+    }
+
+    /// Count the marked cells of given plane.
+    fn count_marked(plane: &RawState) -> u32 {
+        plane.words.iter().map(|word| word.count_ones()).sum()
+    }
+
+    /// Number of bit planes required to store given number of teams.
+    fn planes_count(teams: u8) -> usize {
+        ((teams - 1).ilog2() + 1) as usize
     }
 
     pub fn row(state: &RawState, row_index: Index) -> &RawRow {
-        let row_state = &state[row_index as usize];
-        row_state
+        let start = row_index as usize * state.stride;
+        &state.words[start..start + state.stride]
     }
 
     pub fn row_mut(state: &mut RawState, row_index: Index) -> &mut RawRow {
-        let row_state = &mut state[row_index as usize];
-        row_state
+        let start = row_index as usize * state.stride;
+        &mut state.words[start..start + state.stride]
     }
 
-    pub fn cell(row: &RawRow, col_index: Index) -> State {
+    /// The team owning the cell or [`State::WALL`].
+    pub fn state(
+        planes: &[RawState],
+        walls: &RawState,
+        row_index: Index,
+        col_index: Index,
+    ) -> State {
+        if Self::is_marked(walls, row_index, col_index) {
+            return State::WALL;
+        }
+        Self::cell(planes, row_index, col_index)
+    }
+
+    pub fn cell(planes: &[RawState], row_index: Index, col_index: Index) -> State {
         let (part_index, bit_index) = Self::part_and_bit_index(col_index);
         let cell = 1 << bit_index;
-        if row[part_index] & cell > 0 {
-            State::Lit
-        } else {
-            State::Dark
+        let id = planes
+            .iter()
+            .enumerate()
+            .filter(|(_, plane)| Self::row(plane, row_index)[part_index] & cell > 0)
+            .fold(0, |id, (plane_index, _)| id | (1 << plane_index));
+        State(id)
+    }
+
+    fn set(planes: &mut [RawState], row_index: Index, col_index: Index, state: State) {
+        let (part_index, bit_index) = Self::part_and_bit_index(col_index);
+        let cell = 1 << bit_index;
+        for (plane_index, plane) in planes.iter_mut().enumerate() {
+            let part = &mut Self::row_mut(plane, row_index)[part_index];
+            if state.0 & (1 << plane_index) > 0 {
+                *part |= cell;
+            } else {
+                *part &= !cell;
+            }
         }
     }
 
-    fn debug(f: &mut fmt::Formatter, size: Index, state: &RawState) -> fmt::Result {
-        for row_index in 0..size {
-            let row = state[row_index as usize];
-            let (max_part_index, max_bit) = Self::part_and_bit_index(size);
-            for part_index in 0..=max_part_index {
-                let mut part = row[part_index];
-                let max = if part_index < max_part_index {
-                    ROW_PART_SIZE
-                } else {
-                    max_bit
-                };
-                for _ in 0..max {
-                    f.write_char(if part & 0b1 > 0 { '▣' } else { '▢' })?;
-                    part >>= 1;
-                }
+    fn is_marked(state: &RawState, row_index: Index, col_index: Index) -> bool {
+        let (part_index, bit_index) = Self::part_and_bit_index(col_index);
+        Self::row(state, row_index)[part_index] & (1 << bit_index) > 0
+    }
+
+    fn mark(state: &mut RawState, row_index: Index, col_index: Index) {
+        let (part_index, bit_index) = Self::part_and_bit_index(col_index);
+        Self::row_mut(state, row_index)[part_index] |= 1 << bit_index;
+    }
+
+    fn clear(state: &mut RawState, row_index: Index, col_index: Index) {
+        let (part_index, bit_index) = Self::part_and_bit_index(col_index);
+        Self::row_mut(state, row_index)[part_index] &= !(1 << bit_index);
+    }
+
+    fn debug(
+        f: &mut fmt::Formatter,
+        width: Index,
+        height: Index,
+        planes: &[RawState],
+        walls: &RawState,
+    ) -> fmt::Result {
+        for row_index in 0..height {
+            for col_index in 0..width {
+                let state = Self::state(planes, walls, row_index, col_index);
+                f.write_char(Self::glyph(state))?;
             }
-            writeln!(f, "")?;
+            writeln!(f)?;
         }
         Ok(())
     }
 
-    fn flip(row: &mut RawRow, col_index: Index) {
-        let (part_index, bit_index) = Self::part_and_bit_index(col_index);
-        let cell = 1 << bit_index;
-        let part = &mut row[part_index];
-        *part = *part ^ cell;
+    fn parse_glyph(glyph: char) -> Result<State, Error> {
+        match glyph {
+            '▢' | '.' => Ok(State::DARK),
+            '▣' | '#' => Ok(State::LIT),
+            '▤' => Ok(State(2)),
+            '▥' => Ok(State(3)),
+            '█' | 'X' => Ok(State::WALL),
+            _ => Err(Error::InvalidCells("Unexpected character")),
+        }
+    }
+
+    fn glyph(state: State) -> char {
+        match state {
+            State::DARK => '▢',
+            State::LIT => '▣',
+            State::WALL => '█',
+            State(2) => '▤',
+            _ => '▥',
+        }
     }
 
     fn part_and_bit_index(col_index: Index) -> (usize, usize) {
         let col_index = col_index as usize;
         let part_index = col_index / ROW_PART_SIZE;
-        let bit_index = ROW_PART_SIZE.min(col_index - part_index * 64);
+        let bit_index = col_index % ROW_PART_SIZE;
         (part_index, bit_index)
     }
 }
@@ -204,18 +1073,17 @@ mod tests {
 
     #[test]
     fn should_set_initial_state() {
-        for size in [4, 8, 9, 16, 64, 128, 255] {
-            let board = Board::new(size);
+        for size in [4, 8, 9, 16, 64, 128, 255, 1000] {
+            let board = Board::square(size);
             for i in 0..size {
                 let r0 = board.row(i);
                 for j in 0..size {
-                    let state = if j < size / 2 {
-                        State::Lit
                     // in case of odd size, every odd row has one more cell lit.
-                    } else if j == size / 2 && i % 2 == 1 && size % 2 == 1 {
-                        State::Lit
+                    let is_odd_extra = j == size / 2 && i % 2 == 1 && size % 2 == 1;
+                    let state = if j < size / 2 || is_odd_extra {
+                        State::LIT
                     } else {
-                        State::Dark
+                        State::DARK
                     };
                     assert_eq!(r0.cell(j), state);
                 }
@@ -223,23 +1091,332 @@ mod tests {
         }
     }
 
+    #[test]
+    fn should_split_rectangular_board_across_longer_side() {
+        let wide = Board::new(6, 3);
+        let tall = Board::new(3, 6);
+
+        for i in 0..3 {
+            for j in 0..6 {
+                let state = if j < 3 { State::LIT } else { State::DARK };
+                assert_eq!(wide.cell(i, j), state);
+                assert_eq!(tall.cell(j, i), state);
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "The column index is beyond board size.")]
+    fn should_validate_column_against_width() {
+        let board = Board::new(3, 6);
+
+        board.cell(4, 3);
+    }
+
+    #[test]
+    fn should_allocate_only_required_words() {
+        let board = Board::new(65, 3);
+        assert_eq!(board.raw_state().stride(), 2);
+        assert_eq!(board.raw_state().words().len(), 6);
+
+        let board = Board::new(4000, 2);
+        assert_eq!(board.raw_state().stride(), 63);
+        assert_eq!(board.raw_state().words().len(), 126);
+        assert_eq!(board.cell(1, 1999), State::LIT);
+        assert_eq!(board.cell(1, 2000), State::DARK);
+    }
+
     #[test]
     fn should_flip_the_state_at_location() {
-        let mut board = Board::new(4);
+        let mut board = Board::square(4);
         let cell = board.row(3).cell(2);
-        assert_eq!(cell, State::Dark);
+        assert_eq!(cell, State::DARK);
 
         // when
         board.flip(3, 2);
 
         // then
         let cell = board.row(3).cell(2);
-        assert_eq!(cell, State::Lit);
+        assert_eq!(cell, State::LIT);
+    }
+
+    #[test]
+    fn should_split_the_board_between_more_teams() {
+        let board = Board::with_teams(7, 2, 3);
+
+        let view = format!("\n{:?}", board);
+
+        assert_eq!(
+            view,
+            r#"
+▣▣▢▢▤▤▤
+▣▣▣▢▢▤▤
+Board { width: 7, height: 2, teams: 3 }"#
+        );
+        assert_eq!(board.raw_planes().len(), 2);
+        assert_eq!(board.cell(0, 6), State::team(2));
+    }
+
+    #[test]
+    fn should_convert_cells_between_teams() {
+        let mut board = Board::with_teams(8, 8, 4);
+        assert_eq!(board.cell(0, 0), State::LIT);
+
+        // when
+        board.set_cell(0, 0, State::team(3));
+        board.set_cell(0, 7, State::DARK);
+
+        // then
+        assert_eq!(board.cell(0, 0), State::team(3));
+        assert_eq!(board.cell(0, 7), State::DARK);
+        assert_eq!(board.raw_planes()[0].words()[0] & 0b1, 1);
+        assert_eq!(board.raw_planes()[1].words()[0] & 0b1, 1);
+
+        // when
+        board.set_cell(0, 0, State::LIT);
+
+        // then
+        assert_eq!(board.cell(0, 0), State::LIT);
+        assert_eq!(board.raw_planes()[1].words()[0] & 0b1, 0);
+    }
+
+    #[test]
+    fn should_keep_track_of_team_counts() {
+        let mut board = Board::with_teams(7, 2, 3);
+        assert_eq!(board.counts(), &[4, 5, 5]);
+
+        // when
+        board.flip(0, 0);
+        board.flip(0, 4);
+        board.set_cell(1, 6, State::DARK);
+
+        // then
+        assert_eq!(board.counts(), &[6, 5, 3]);
+        assert_eq!(board.count(State::team(2)), 3);
+        assert_eq!(board.territory(State::DARK), 6.0 / 14.0);
+    }
+
+    #[test]
+    fn should_track_changed_cells() {
+        let mut board = Board::new(70, 2);
+        assert_eq!(board.take_dirty().len(), 140);
+        assert!(!board.is_dirty());
+
+        // when
+        board.flip(1, 66);
+        board.set_cell(0, 3, State::DARK);
+        board.set_cell(0, 2, State::LIT);
+
+        // then
+        assert!(board.is_dirty());
+        assert_eq!(board.take_dirty(), vec![(0, 3), (1, 66)]);
+        assert_eq!(board.take_dirty(), vec![]);
+
+        // when
+        let mut other = board.clone();
+        other.flip(0, 69);
+        let copy = other.copy_cells();
+        board.restore_from(&copy);
+
+        // then
+        assert!(copy.dirty.words.is_empty());
+        assert_eq!(board, other);
+        assert_eq!(board.take_dirty(), vec![(0, 69)]);
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn should_serialize_states_including_walls() {
+        let states = [State::DARK, State::LIT, State::team(3), State::WALL];
+
+        // when
+        let json = serde_json::to_string(&states).unwrap();
+        let deserialized: Vec<State> = serde_json::from_str(&json).unwrap();
+
+        // then
+        assert_eq!(json, "[0,1,3,255]");
+        assert_eq!(deserialized, states);
+        assert!(serde_json::from_str::<State>("4").is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn should_serialize_board_as_runs_of_cells() {
+        let mut board = Board::with_teams(7, 2, 3);
+        board.flip(1, 6);
+        board.set_cell(0, 3, State::WALL);
+
+        // when
+        let json = serde_json::to_string(&board).unwrap();
+        let deserialized: Board = serde_json::from_str(&json).unwrap();
+
+        // then
+        assert_eq!(
+            json,
+            r#"{"width":7,"height":2,"teams":3,"cells":"2b1a1x3c3b2a1c1b"}"#
+        );
+        assert_eq!(deserialized, board);
+        let invalid = r#"{"width":7,"height":2,"teams":3,"cells":"2b2a3c3b2a1c1d"}"#;
+        assert!(serde_json::from_str::<Board>(invalid).is_err());
+    }
+
+    #[test]
+    fn should_load_board_from_ascii() {
+        let ascii = r#"
+            ##..#
+            #.▢▣▤
+        "#;
+
+        // when
+        let board = Board::from_ascii(ascii).unwrap();
+
+        // then
+        let view = format!("\n{:?}", board);
+        assert_eq!(
+            view,
+            r#"
+▣▣▢▢▣
+▣▢▢▣▤
+Board { width: 5, height: 2, teams: 3 }"#
+        );
+        assert_eq!(
+            Board::from_ascii("##.\n#."),
+            Err(Error::InvalidCells("Rows of different lengths"))
+        );
+        assert_eq!(
+            Board::from_ascii("#x\n.."),
+            Err(Error::InvalidCells("Unexpected character"))
+        );
+    }
+
+    #[test]
+    fn should_parse_debug_output_back() {
+        let boards = [
+            Board::square(5),
+            Board::new(4, 5),
+            Board::with_teams(7, 2, 3),
+            Board::with_layout(9, 6, 4, &Layout::Quadrants),
+            Board::with_layout(70, 3, 3, &Layout::Random { seed: 3 }),
+        ];
+
+        for board in boards {
+            // when
+            let parsed = format!("{:?}", board).parse::<Board>();
+
+            // then
+            assert_eq!(parsed, Ok(board));
+        }
+    }
+
+    #[test]
+    fn should_compare_boards_with_ascii_fixtures() {
+        let mut board = Board::square(4);
+
+        // when
+        board.flip(3, 2);
+
+        // then
+        let expected = r#"
+            ##..
+            ##..
+            ##..
+            ###.
+        "#;
+        assert_eq!(board, expected.parse().unwrap());
+        assert_eq!(Board::new(6, 3), "###...\n###...\n###...".parse().unwrap());
+    }
+
+    #[test]
+    fn should_validate_board_summary() {
+        let square = Board::from_ascii("##..\n##..\n##..\n##..\nBoard { size: 4 }");
+        let more_teams = Board::from_ascii("##\n..\nBoard { width: 2, height: 2, teams: 4 }");
+
+        assert_eq!(square, Ok(Board::square(4)));
+        assert_eq!(more_teams.map(|board| board.teams()), Ok(4));
+        assert_eq!(
+            Board::from_ascii("##\n..\nBoard { size: 3 }"),
+            Err(Error::InvalidCells("Dimensions do not match the cells"))
+        );
+        assert_eq!(
+            Board::from_ascii("#▤\n..\nBoard { size: 2, teams: 2 }"),
+            Err(Error::UnknownTeam(2))
+        );
+        assert_eq!(
+            Board::from_ascii("##\n..\nBoard { depth: 2 }"),
+            Err(Error::InvalidCells("Invalid board summary"))
+        );
+    }
+
+    #[test]
+    fn should_keep_walls_intact() {
+        let mut board: Board = "#X..\n#X..\n##█.".parse().unwrap();
+        board.take_dirty();
+
+        // when
+        board.flip(0, 1);
+        board.set_cell(2, 2, State::DARK);
+        board.set_cell(0, 3, State::WALL);
+
+        // then
+        assert_eq!(board.cell(0, 1), State::WALL);
+        assert_eq!(board.count(State::WALL), 3);
+        assert_eq!(board.counts(), &[5, 4]);
+        assert_eq!(board.territory(State::LIT), 4.0 / 9.0);
+        assert_eq!(board.take_dirty(), vec![(0, 3), (2, 2)]);
+        assert_eq!(
+            format!("{:?}", board),
+            "▣█▢█\n▣█▢▢\n▣▣▢▢\nBoard { width: 4, height: 3, teams: 2 }"
+        );
+    }
+
+    #[test]
+    fn should_have_no_territory_on_board_of_walls() {
+        // when
+        let board: Board = "XX\nXX".parse().unwrap();
+
+        // then
+        assert_eq!(board.count(State::WALL), 4);
+        assert_eq!(board.territory(State::LIT), 0.0);
+        assert_eq!(board.territory(State::DARK), 0.0);
+    }
+
+    #[test]
+    #[should_panic(expected = "The team 2 is not playing on this board.")]
+    fn should_not_set_cell_to_unknown_team() {
+        let mut board = Board::new(4, 4);
+
+        board.set_cell(0, 0, State::team(2));
+    }
+
+    #[test]
+    fn should_return_errors_instead_of_panicking() {
+        let mut board = Board::new(4, 3);
+
+        assert_eq!(
+            Board::try_new(1, 5).err(),
+            Some(Error::BoardTooSmall {
+                width: 1,
+                height: 5
+            })
+        );
+        assert_eq!(
+            Board::try_with_teams(4, 4, 5).err(),
+            Some(Error::UnsupportedTeams(5))
+        );
+        assert_eq!(board.get(3, 0), Err(Error::RowOutOfBounds(3)));
+        assert_eq!(board.get(2, 4), Err(Error::ColumnOutOfBounds(4)));
+        assert_eq!(board.try_flip(0, 4), Err(Error::ColumnOutOfBounds(4)));
+        assert_eq!(
+            board.try_set_cell(0, 0, State::team(3)),
+            Err(Error::UnknownTeam(3))
+        );
+        assert_eq!(State::try_team(4), Err(Error::UnknownTeam(4)));
+        assert_eq!(board.get(2, 3), Ok(State::DARK));
     }
 
     #[test]
     fn should_debug_board_properly() {
-        let board = Board::new(5);
+        let board = Board::square(5);
 
         let view = format!("\n{:?}", board);
 
@@ -251,7 +1428,25 @@ mod tests {
 ▣▣▢▢▢
 ▣▣▣▢▢
 ▣▣▢▢▢
-Board { size: 5 }"#
+Board { width: 5, height: 5, teams: 2 }"#
+        );
+    }
+
+    #[test]
+    fn should_debug_tall_board_properly() {
+        let board = Board::new(4, 5);
+
+        let view = format!("\n{:?}", board);
+
+        assert_eq!(
+            view,
+            r#"
+▣▣▣▣
+▣▣▣▣
+▢▣▢▣
+▢▢▢▢
+▢▢▢▢
+Board { width: 4, height: 5, teams: 2 }"#
         );
     }
 }
@@ -0,0 +1,665 @@
+//! Gameplay configuration.
+//!
+//! All the tunable physics constants are gathered here,
+//! so that the gameplay can be adjusted without changing the engine.
+//! The defaults reproduce the classic game.
+
+use crate::board::{State, MAX_TEAMS};
+use crate::error::{Error, OrPanic};
+use crate::game::{Coordinate, Timestamp};
+use crate::snapshot::{Encode, Reader, Writer};
+
+/// Game configuration passed to [`crate::game::Game::new`].
+///
+/// Use [`GameConfig::builder`] to override selected values
+/// or [`GameConfig::default`] for the classic gameplay.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GameConfig {
+    step_ms: Timestamp,
+    initial_speed: Coordinate,
+    max_speed: Coordinate,
+    speed_up: Coordinate,
+    initial_angles: [Coordinate; MAX_TEAMS as usize],
+    ball_radius_ratio: Coordinate,
+    angle_skew: Coordinate,
+    seed: Option<u64>,
+    bounce_jitter: Coordinate,
+    time_limit: Option<Timestamp>,
+    territory_threshold: Option<f32>,
+    flips_to_win: Option<u32>,
+    rewind_capacity: usize,
+    ball_collisions: BallCollisions,
+    boundaries: Boundaries,
+    paddle_width: Coordinate,
+    paddle_speed: Coordinate,
+    paddle_max_angle: Coordinate,
+}
+
+/// The way the balls interact with each other.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BallCollisions {
+    /// The balls pass through each other (the classic game).
+    #[default]
+    Off,
+    /// All the balls bounce off each other.
+    Bounce,
+    /// Only the balls of the same team bounce off each other.
+    SameTeamOnly,
+}
+
+impl BallCollisions {
+    /// Return `true` if the balls of given teams bounce off each other.
+    pub fn applies(self, a: State, b: State) -> bool {
+        match self {
+            BallCollisions::Off => false,
+            BallCollisions::Bounce => true,
+            BallCollisions::SameTeamOnly => a == b,
+        }
+    }
+}
+
+/// The way the balls interact with the edges of the viewport.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Boundaries {
+    /// The balls bounce off the edges (the classic game).
+    #[default]
+    Reflect,
+    /// The balls leaving through one edge come back through the opposite one.
+    ///
+    /// The arena is a torus, i.e. a ball straddling an edge hits the cells
+    /// on both sides of it (see also [`crate::game::Game::ball_images`]).
+    Wrap,
+    /// A ball touching an edge is lost and the round is over.
+    ///
+    /// The team of the lost ball can't win, the best of the remaining
+    /// teams (see [`crate::game::Game::scoreboard`]) wins the round.
+    Absorb,
+}
+
+impl Default for GameConfig {
+    fn default() -> Self {
+        Self {
+            step_ms: 10,
+            initial_speed: 0.5,
+            max_speed: 1.0,
+            speed_up: 0.005,
+            initial_angles: [220.0, 40.0, 130.0, 310.0],
+            ball_radius_ratio: 0.5,
+            angle_skew: 3.0,
+            seed: None,
+            bounce_jitter: 0.0,
+            time_limit: None,
+            territory_threshold: None,
+            flips_to_win: None,
+            rewind_capacity: 0,
+            ball_collisions: BallCollisions::Off,
+            boundaries: Boundaries::Reflect,
+            paddle_width: 4.0,
+            paddle_speed: 0.8,
+            paddle_max_angle: 60.0,
+        }
+    }
+}
+
+impl GameConfig {
+    /// Start building a configuration from the default values.
+    pub fn builder() -> GameConfigBuilder {
+        GameConfigBuilder {
+            config: Self::default(),
+            unknown_team: None,
+        }
+    }
+
+    /// Length of a single physics step (milliseconds).
+    pub fn step_ms(&self) -> Timestamp {
+        self.step_ms
+    }
+
+    /// Speed of newly launched balls (coordinate units per millisecond).
+    pub fn initial_speed(&self) -> Coordinate {
+        self.initial_speed
+    }
+
+    /// The speed the balls can accelerate to (coordinate units per millisecond).
+    pub fn max_speed(&self) -> Coordinate {
+        self.max_speed
+    }
+
+    /// Speed gained on every bounce (coordinate units per millisecond).
+    pub fn speed_up(&self) -> Coordinate {
+        self.speed_up
+    }
+
+    /// Initial heading of the ball of given team (degrees, clockwise from right).
+    ///
+    /// Returns `None` if the state is not a team (e.g. [`State::WALL`]).
+    pub fn initial_angle(&self, team: State) -> Option<Coordinate> {
+        self.initial_angles.get(team.id() as usize).copied()
+    }
+
+    /// Ball radius relative to the average cell dimension.
+    pub fn ball_radius_ratio(&self) -> Coordinate {
+        self.ball_radius_ratio
+    }
+
+    /// Skew of the rebound angle at the initial speed (degrees).
+    pub fn angle_skew(&self) -> Coordinate {
+        self.angle_skew
+    }
+
+    /// Seed of the random start positions and headings.
+    pub fn seed(&self) -> Option<u64> {
+        self.seed
+    }
+
+    /// Maximal random deviation of the rebound angle (degrees).
+    pub fn bounce_jitter(&self) -> Coordinate {
+        self.bounce_jitter
+    }
+
+    /// Game time after which the game is over (milliseconds).
+    pub fn time_limit(&self) -> Option<Timestamp> {
+        self.time_limit
+    }
+
+    /// Fraction of the board a team has to own to win the game.
+    pub fn territory_threshold(&self) -> Option<f32> {
+        self.territory_threshold
+    }
+
+    /// Number of cells a team has to flip to win the game.
+    pub fn flips_to_win(&self) -> Option<u32> {
+        self.flips_to_win
+    }
+
+    /// Number of past states kept to rewind the game.
+    pub fn rewind_capacity(&self) -> usize {
+        self.rewind_capacity
+    }
+
+    /// The way the balls interact with each other.
+    pub fn ball_collisions(&self) -> BallCollisions {
+        self.ball_collisions
+    }
+
+    /// The way the balls interact with the edges of the viewport.
+    pub fn boundaries(&self) -> Boundaries {
+        self.boundaries
+    }
+
+    /// Width of the paddles (in cells).
+    pub fn paddle_width(&self) -> Coordinate {
+        self.paddle_width
+    }
+
+    /// Speed of the paddles (coordinate units per millisecond).
+    pub fn paddle_speed(&self) -> Coordinate {
+        self.paddle_speed
+    }
+
+    /// Rebound angle off the end of a paddle (degrees from the vertical).
+    pub fn paddle_max_angle(&self) -> Coordinate {
+        self.paddle_max_angle
+    }
+}
+
+/// Builder of the [`GameConfig`].
+#[derive(Debug, Clone)]
+pub struct GameConfigBuilder {
+    config: GameConfig,
+    /// The state passed to [`GameConfigBuilder::initial_angle`] that is not a team.
+    unknown_team: Option<u8>,
+}
+
+impl GameConfigBuilder {
+    /// Set the length of a single physics step (milliseconds).
+    ///
+    /// Shorter steps give more accurate collisions at the cost of
+    /// more calculations per tick. The step can't be longer than a second.
+    pub fn step_ms(mut self, step_ms: Timestamp) -> Self {
+        self.config.step_ms = step_ms;
+        self
+    }
+
+    /// Set the speed of newly launched balls (coordinate units per millisecond).
+    pub fn initial_speed(mut self, speed: Coordinate) -> Self {
+        self.config.initial_speed = speed;
+        self
+    }
+
+    /// Set the speed the balls can accelerate to (coordinate units per millisecond).
+    pub fn max_speed(mut self, speed: Coordinate) -> Self {
+        self.config.max_speed = speed;
+        self
+    }
+
+    /// Set the speed gained on every bounce (coordinate units per millisecond).
+    pub fn speed_up(mut self, speed_up: Coordinate) -> Self {
+        self.config.speed_up = speed_up;
+        self
+    }
+
+    /// Set the initial heading of the ball of given team (degrees, clockwise from right).
+    ///
+    /// The states that are not teams (e.g. [`State::WALL`]) are reported
+    /// by [`GameConfigBuilder::try_build`].
+    pub fn initial_angle(mut self, team: State, angle: Coordinate) -> Self {
+        match self.config.initial_angles.get_mut(team.id() as usize) {
+            Some(initial_angle) => *initial_angle = angle,
+            None => self.unknown_team = Some(team.id()),
+        }
+        self
+    }
+
+    /// Set the ball radius relative to the average cell dimension.
+    ///
+    /// `0.5` gives a ball that fits exactly into a square cell.
+    pub fn ball_radius_ratio(mut self, ratio: Coordinate) -> Self {
+        self.config.ball_radius_ratio = ratio;
+        self
+    }
+
+    /// Set the skew of the rebound angle at the initial speed (degrees).
+    ///
+    /// The skew grows proportionally to the speed of the ball
+    /// and is rounded down to full degrees.
+    pub fn angle_skew(mut self, skew: Coordinate) -> Self {
+        self.config.angle_skew = skew;
+        self
+    }
+
+    /// Set the seed randomizing the start positions and headings of the balls.
+    ///
+    /// Without the seed the balls always start in the middle of their
+    /// teams' stripes at the configured initial angles.
+    /// The same seed and the same sequence of ticks reproduce the same game.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.config.seed = Some(seed);
+        self
+    }
+
+    /// Set the maximal random deviation of the rebound angle (degrees).
+    ///
+    /// The deviation is drawn from the seeded generator (seed `0` if not set),
+    /// so the game stays deterministic.
+    pub fn bounce_jitter(mut self, jitter: Coordinate) -> Self {
+        self.config.bounce_jitter = jitter;
+        self
+    }
+
+    /// Finish the game after given game time (milliseconds).
+    ///
+    /// The team owning the most cells wins, unless there is a tie.
+    pub fn time_limit(mut self, time_limit: Timestamp) -> Self {
+        self.config.time_limit = Some(time_limit);
+        self
+    }
+
+    /// Finish the game once a team owns given fraction of the board (e.g. `0.75`).
+    pub fn territory_threshold(mut self, threshold: f32) -> Self {
+        self.config.territory_threshold = Some(threshold);
+        self
+    }
+
+    /// Finish the game once a team flips given number of cells.
+    pub fn flips_to_win(mut self, flips: u32) -> Self {
+        self.config.flips_to_win = Some(flips);
+        self
+    }
+
+    /// Set the number of past states kept to rewind the game (see [`crate::game::Game::rewind_to`]).
+    ///
+    /// A state is recorded after every tick, so the capacity together with
+    /// the tick rate limits how far back the game can be rewound.
+    /// `0` (the default) disables the rewinding.
+    pub fn rewind_capacity(mut self, capacity: usize) -> Self {
+        self.config.rewind_capacity = capacity;
+        self
+    }
+
+    /// Set the way the balls interact with each other.
+    ///
+    /// The colliding balls bounce elastically, i.e. they exchange
+    /// the momentum along the line connecting their centers.
+    /// The resulting speeds are kept between the initial and the maximal speed.
+    pub fn ball_collisions(mut self, collisions: BallCollisions) -> Self {
+        self.config.ball_collisions = collisions;
+        self
+    }
+
+    /// Set the way the balls interact with the edges of the viewport.
+    pub fn boundaries(mut self, boundaries: Boundaries) -> Self {
+        self.config.boundaries = boundaries;
+        self
+    }
+
+    /// Set the width of the paddles (in cells, see [`crate::game::Game::add_paddle`]).
+    ///
+    /// The paddles are half a cell high.
+    pub fn paddle_width(mut self, width: Coordinate) -> Self {
+        self.config.paddle_width = width;
+        self
+    }
+
+    /// Set the speed the paddles follow their targets with (coordinate units per millisecond).
+    pub fn paddle_speed(mut self, speed: Coordinate) -> Self {
+        self.config.paddle_speed = speed;
+        self
+    }
+
+    /// Set the rebound angle off the end of a paddle (degrees from the vertical).
+    ///
+    /// A ball hitting the middle of the paddle bounces straight back,
+    /// the further from the middle it hits, the steeper the rebound
+    /// (up to this angle at the very end of the paddle).
+    /// The approach angle doesn't matter.
+    pub fn paddle_max_angle(mut self, angle: Coordinate) -> Self {
+        self.config.paddle_max_angle = angle;
+        self
+    }
+
+    /// Finish building the configuration.
+    ///
+    /// Panics if the configuration is invalid (see [`GameConfigBuilder::try_build`]).
+    pub fn build(self) -> GameConfig {
+        self.try_build().or_panic()
+    }
+
+    /// Finish building the configuration.
+    ///
+    /// Returns an error if the step length is zero or over a second,
+    /// the speeds are not positive or finite, the ball radius, the angle skew
+    /// or the jitter are invalid,
+    /// the territory threshold is not a fraction of the board,
+    /// the paddles are invalid or an initial angle is set for an unknown team.
+    pub fn try_build(self) -> Result<GameConfig, Error> {
+        let config = self.config;
+        if let Some(team) = self.unknown_team {
+            return Err(Error::UnknownTeam(team));
+        }
+        if config.step_ms == 0 {
+            return Err(Error::InvalidConfig("The physics step has to be positive"));
+        }
+        if config.step_ms > 1000 {
+            return Err(Error::InvalidConfig(
+                "The physics step can't be longer than a second",
+            ));
+        }
+        if !(config.initial_speed > 0.0 && config.max_speed >= config.initial_speed) {
+            return Err(Error::InvalidConfig(
+                "The speed has to be positive and not greater than the maximal speed",
+            ));
+        }
+        if !config.max_speed.is_finite() {
+            return Err(Error::InvalidConfig("The maximal speed has to be finite"));
+        }
+        if !(config.speed_up >= 0.0 && config.speed_up.is_finite()) {
+            return Err(Error::InvalidConfig(
+                "The speed-up has to be non-negative and finite",
+            ));
+        }
+        // note the negated comparison rejects `NaN` ratio as well.
+        if !(config.ball_radius_ratio > 0.0 && config.ball_radius_ratio.is_finite()) {
+            return Err(Error::InvalidConfig(
+                "The ball radius ratio has to be positive and finite",
+            ));
+        }
+        if config.angle_skew.is_nan() {
+            return Err(Error::InvalidConfig("The angle skew has to be a number"));
+        }
+        if config.bounce_jitter.is_nan() || config.bounce_jitter < 0.0 {
+            return Err(Error::InvalidConfig(
+                "The bounce jitter has to be non-negative",
+            ));
+        }
+        if let Some(threshold) = config.territory_threshold {
+            if !(threshold > 0.0 && threshold <= 1.0) {
+                return Err(Error::InvalidConfig(
+                    "The territory threshold has to be between 0 and 1",
+                ));
+            }
+        }
+        if !(config.paddle_width > 0.0 && config.paddle_speed > 0.0) {
+            return Err(Error::InvalidConfig(
+                "The paddle width and speed have to be positive",
+            ));
+        }
+        if !(0.0..90.0).contains(&config.paddle_max_angle) {
+            return Err(Error::InvalidConfig(
+                "The paddle rebound angle has to be between 0 and 90 degrees",
+            ));
+        }
+        Ok(config)
+    }
+}
+
+impl Encode for GameConfig {
+    fn encode(&self, writer: &mut Writer) {
+        self.step_ms.encode(writer);
+        self.initial_speed.encode(writer);
+        self.max_speed.encode(writer);
+        self.speed_up.encode(writer);
+        self.initial_angles.encode(writer);
+        self.ball_radius_ratio.encode(writer);
+        self.angle_skew.encode(writer);
+        self.seed.encode(writer);
+        self.bounce_jitter.encode(writer);
+        self.time_limit.encode(writer);
+        self.territory_threshold.encode(writer);
+        self.flips_to_win.encode(writer);
+        (self.rewind_capacity as u32).encode(writer);
+        self.ball_collisions.encode(writer);
+        self.boundaries.encode(writer);
+        self.paddle_width.encode(writer);
+        self.paddle_speed.encode(writer);
+        self.paddle_max_angle.encode(writer);
+    }
+
+    fn decode(reader: &mut Reader) -> Result<Self, Error> {
+        let config = GameConfig {
+            step_ms: Encode::decode(reader)?,
+            initial_speed: Encode::decode(reader)?,
+            max_speed: Encode::decode(reader)?,
+            speed_up: Encode::decode(reader)?,
+            initial_angles: Encode::decode(reader)?,
+            ball_radius_ratio: Encode::decode(reader)?,
+            angle_skew: Encode::decode(reader)?,
+            seed: Encode::decode(reader)?,
+            bounce_jitter: Encode::decode(reader)?,
+            time_limit: Encode::decode(reader)?,
+            territory_threshold: Encode::decode(reader)?,
+            flips_to_win: Encode::decode(reader)?,
+            rewind_capacity: u32::decode(reader)? as usize,
+            ball_collisions: Encode::decode(reader)?,
+            boundaries: Encode::decode(reader)?,
+            paddle_width: Encode::decode(reader)?,
+            paddle_speed: Encode::decode(reader)?,
+            paddle_max_angle: Encode::decode(reader)?,
+        };
+        GameConfigBuilder {
+            config,
+            unknown_team: None,
+        }
+        .try_build()
+    }
+}
+
+impl Encode for BallCollisions {
+    fn encode(&self, writer: &mut Writer) {
+        let mode: u8 = match self {
+            BallCollisions::Off => 0,
+            BallCollisions::Bounce => 1,
+            BallCollisions::SameTeamOnly => 2,
+        };
+        mode.encode(writer);
+    }
+
+    fn decode(reader: &mut Reader) -> Result<Self, Error> {
+        match u8::decode(reader)? {
+            0 => Ok(BallCollisions::Off),
+            1 => Ok(BallCollisions::Bounce),
+            2 => Ok(BallCollisions::SameTeamOnly),
+            _ => Err(Error::CorruptedSnapshot("Unknown ball collisions mode")),
+        }
+    }
+}
+
+impl Encode for Boundaries {
+    fn encode(&self, writer: &mut Writer) {
+        let mode: u8 = match self {
+            Boundaries::Reflect => 0,
+            Boundaries::Wrap => 1,
+            Boundaries::Absorb => 2,
+        };
+        mode.encode(writer);
+    }
+
+    fn decode(reader: &mut Reader) -> Result<Self, Error> {
+        match u8::decode(reader)? {
+            0 => Ok(Boundaries::Reflect),
+            1 => Ok(Boundaries::Wrap),
+            2 => Ok(Boundaries::Absorb),
+            _ => Err(Error::CorruptedSnapshot("Unknown boundaries mode")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_override_selected_values() {
+        let config = GameConfig::builder()
+            .step_ms(5)
+            .initial_angle(State::DARK, 180.0)
+            .build();
+
+        assert_eq!(config.step_ms(), 5);
+        assert_eq!(config.initial_angle(State::DARK), Some(180.0));
+        assert_eq!(config.initial_angle(State::LIT), Some(40.0));
+        assert_eq!(config.initial_angle(State::WALL), None);
+        assert_eq!(
+            config.initial_speed(),
+            GameConfig::default().initial_speed()
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "The speed has to be positive")]
+    fn should_reject_max_speed_below_initial_speed() {
+        GameConfig::builder()
+            .initial_speed(1.0)
+            .max_speed(0.5)
+            .build();
+    }
+
+    #[test]
+    fn should_reject_territory_threshold_above_whole_board() {
+        let result = GameConfig::builder().territory_threshold(1.5).try_build();
+
+        assert_eq!(
+            result,
+            Err(Error::InvalidConfig(
+                "The territory threshold has to be between 0 and 1"
+            ))
+        );
+    }
+
+    #[test]
+    fn should_reject_infinite_speed() {
+        let result = GameConfig::builder()
+            .max_speed(Coordinate::INFINITY)
+            .try_build();
+
+        assert_eq!(
+            result,
+            Err(Error::InvalidConfig("The maximal speed has to be finite"))
+        );
+    }
+
+    #[test]
+    fn should_reject_invalid_speed_up() {
+        for speed_up in [-0.2, Coordinate::NAN, Coordinate::INFINITY] {
+            let result = GameConfig::builder().speed_up(speed_up).try_build();
+
+            assert_eq!(
+                result,
+                Err(Error::InvalidConfig(
+                    "The speed-up has to be non-negative and finite"
+                ))
+            );
+        }
+    }
+
+    #[test]
+    fn should_reject_invalid_ball_radius_ratio() {
+        for ratio in [0.0, -0.5, Coordinate::NAN, Coordinate::INFINITY] {
+            let result = GameConfig::builder().ball_radius_ratio(ratio).try_build();
+
+            assert_eq!(
+                result,
+                Err(Error::InvalidConfig(
+                    "The ball radius ratio has to be positive and finite"
+                ))
+            );
+        }
+    }
+
+    #[test]
+    fn should_reject_invalid_bounce_jitter() {
+        for jitter in [-1.0, Coordinate::NAN] {
+            let result = GameConfig::builder().bounce_jitter(jitter).try_build();
+
+            assert_eq!(
+                result,
+                Err(Error::InvalidConfig(
+                    "The bounce jitter has to be non-negative"
+                ))
+            );
+        }
+    }
+
+    #[test]
+    fn should_reject_nan_angle_skew() {
+        let result = GameConfig::builder()
+            .angle_skew(Coordinate::NAN)
+            .try_build();
+
+        assert_eq!(
+            result,
+            Err(Error::InvalidConfig("The angle skew has to be a number"))
+        );
+    }
+
+    #[test]
+    fn should_reject_initial_angle_of_unknown_team() {
+        let result = GameConfig::builder()
+            .initial_angle(State::WALL, 90.0)
+            .try_build();
+
+        assert_eq!(result, Err(Error::UnknownTeam(State::WALL.id())));
+    }
+
+    #[test]
+    fn should_reject_zero_step() {
+        let result = GameConfig::builder().step_ms(0).try_build();
+
+        assert_eq!(
+            result,
+            Err(Error::InvalidConfig("The physics step has to be positive"))
+        );
+    }
+
+    #[test]
+    fn should_reject_too_long_step() {
+        let result = GameConfig::builder().step_ms(u64::MAX / 10).try_build();
+
+        assert_eq!(
+            result,
+            Err(Error::InvalidConfig(
+                "The physics step can't be longer than a second"
+            ))
+        );
+    }
+}
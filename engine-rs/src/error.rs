@@ -0,0 +1,99 @@
+//! Engine errors.
+//!
+//! All the fallible operations (the `try_*` and `get` methods)
+//! return [`Error`] instead of panicking, which is crucial
+//! when the engine is running inside of a WASM instance.
+
+use std::fmt;
+
+use crate::board::Index;
+use crate::game::Timestamp;
+
+/// An error returned by the fallible engine operations.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    /// The board has to have at least 2 rows and 2 columns.
+    BoardTooSmall {
+        /// Requested width of the board.
+        width: Index,
+        /// Requested height of the board.
+        height: Index,
+    },
+    /// The number of teams is not supported (see [`crate::board::MAX_TEAMS`]).
+    UnsupportedTeams(u8),
+    /// The team is not playing on the board (or the team id is too big).
+    UnknownTeam(u8),
+    /// The row index is beyond board size.
+    RowOutOfBounds(Index),
+    /// The column index is beyond board size.
+    ColumnOutOfBounds(Index),
+    /// The viewport is too small to draw a cell.
+    ViewportTooSmall,
+    /// The time passed to the tick did not move forward.
+    TimeNotAdvanced {
+        /// Time of the previous tick.
+        previous: Timestamp,
+        /// Time of the rejected tick.
+        current: Timestamp,
+    },
+    /// The game configuration is invalid.
+    InvalidConfig(&'static str),
+    /// The snapshot was created with an unsupported layout version.
+    UnsupportedSnapshotVersion(u16),
+    /// The snapshot data is damaged or incomplete.
+    CorruptedSnapshot(&'static str),
+    /// The game state at given game time is not recorded (or not anymore).
+    NotRecorded(Timestamp),
+    /// The textual representation of the board cells is invalid.
+    InvalidCells(&'static str),
+    /// The team has no paddle.
+    NoPaddle(u8),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::BoardTooSmall { width, height } => {
+                write!(f, "The board is too small ({width}x{height}).")
+            }
+            Error::UnsupportedTeams(teams) => {
+                write!(f, "The number of teams is not supported ({teams}).")
+            }
+            Error::UnknownTeam(team) => write!(f, "The team {team} is not playing on this board."),
+            Error::RowOutOfBounds(_) => write!(f, "The row index is beyond board size."),
+            Error::ColumnOutOfBounds(_) => write!(f, "The column index is beyond board size."),
+            Error::ViewportTooSmall => write!(f, "The viewport size is too small to draw a cell."),
+            Error::TimeNotAdvanced { previous, current } => {
+                write!(f, "The time did not change! ({current} <= {previous})")
+            }
+            Error::InvalidConfig(reason) => write!(f, "Invalid game configuration: {reason}"),
+            Error::UnsupportedSnapshotVersion(version) => {
+                write!(f, "The snapshot version {version} is not supported.")
+            }
+            Error::CorruptedSnapshot(reason) => write!(f, "Corrupted snapshot: {reason}"),
+            Error::NotRecorded(time) => {
+                write!(f, "The game state at {time} ms is not recorded.")
+            }
+            Error::InvalidCells(reason) => write!(f, "Invalid board cells: {reason}"),
+            Error::NoPaddle(team) => write!(f, "The team {team} has no paddle."),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// Turn the fallible operations into their panicking counterparts.
+pub(crate) trait OrPanic<T> {
+    /// Unwrap the value or panic with the error message.
+    fn or_panic(self) -> T;
+}
+
+impl<T> OrPanic<T> for Result<T, Error> {
+    #[track_caller]
+    fn or_panic(self) -> T {
+        match self {
+            Ok(value) => value,
+            Err(error) => panic!("{error}"),
+        }
+    }
+}
@@ -0,0 +1,78 @@
+//! Game events.
+//!
+//! Every physics step may change the board and the balls.
+//! Instead of diffing the whole state, the front-ends can
+//! react to the events emitted by the game (play sounds,
+//! spawn particles, collect statistics, etc).
+//! See [`crate::game::Game::drain_events`].
+
+use crate::board::{Index, State};
+use crate::game::{Coordinate, Position};
+
+/// Something that happened during a physics step.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Event {
+    /// A cell was converted to the team of the ball that hit it.
+    CellFlipped {
+        /// Row of the cell.
+        row: Index,
+        /// Column of the cell.
+        col: Index,
+        /// The team the cell belongs to now.
+        by_team: State,
+    },
+    /// A ball bounced off an obstacle.
+    BallBounced {
+        /// Index of the ball (see [`crate::game::Game::balls`]).
+        ball: usize,
+        /// What the ball bounced off.
+        kind: BounceKind,
+        /// Position of the ball at the moment of the bounce.
+        position: Position,
+    },
+    /// Two balls bounced off each other
+    /// (see [`crate::config::GameConfigBuilder::ball_collisions`]).
+    BallsCollided {
+        /// Index of the first ball (see [`crate::game::Game::balls`]).
+        ball: usize,
+        /// Index of the other ball (always greater than `ball`).
+        other: usize,
+        /// The point of contact.
+        position: Position,
+    },
+    /// A ball touched the absorbing edge of the viewport and the round is over
+    /// (see [`crate::config::Boundaries::Absorb`]).
+    BallAbsorbed {
+        /// Index of the ball (see [`crate::game::Game::balls`]).
+        ball: usize,
+        /// Position of the ball at the moment it was absorbed.
+        position: Position,
+    },
+    /// A ball changed its speed (i.e. it accelerated after a bounce).
+    SpeedChanged {
+        /// Index of the ball (see [`crate::game::Game::balls`]).
+        ball: usize,
+        /// New speed of the ball (coordinate units per millisecond).
+        speed: Coordinate,
+    },
+    /// One of the end conditions is met and the game is over.
+    GameFinished {
+        /// The winning team, `None` in case of a draw.
+        winner: Option<State>,
+    },
+}
+
+/// The kind of obstacle a ball bounced off.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum BounceKind {
+    /// The edge of the viewport.
+    Wall,
+    /// A cell of another team.
+    Cell,
+    /// A wall cell of the board.
+    Obstacle,
+    /// A paddle (see [`crate::game::Game::add_paddle`]).
+    Paddle,
+}
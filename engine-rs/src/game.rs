@@ -1,14 +1,23 @@
 //! Game physics.
 //!
 //! The components here are responsible for moving objects
-//! over the board. In particular we track:
-//! 1. The "lit" ball.
-//! 2. The "dark" ball.
+//! over the board. In particular we track any number of balls,
+//! each of them belonging to a team (see [`board::State`]),
+//! e.g. the "lit" ball and the "dark" ball.
 //!
 //! Since collisions and physics are tightly coupled with
 //! how objects are going to be rendered, this part of the
 //! code is aware of the sizes of objects within the coordinate space.
-use crate::board::{self, Board};
+use std::cmp::Reverse;
+
+use crate::board::{self, Board, MAX_TEAMS};
+use crate::config::{BallCollisions, Boundaries, GameConfig};
+use crate::error::{Error, OrPanic};
+use crate::event::{BounceKind, Event};
+use crate::history::History;
+use crate::rng::Rng;
+use crate::score::{GameStatus, Score};
+use crate::snapshot::{self, Encode, Reader, Writer};
 
 /// Space coordinate type.
 ///
@@ -22,8 +31,21 @@ pub type Coordinate = f32;
 /// Timestamp type (milliseconds).
 pub type Timestamp = u64;
 
+/// The most physics steps a single tick can run.
+///
+/// A long gap between the ticks (e.g. a resumed background tab)
+/// would otherwise freeze the caller while the whole gap is simulated.
+pub const MAX_STEPS_PER_TICK: Timestamp = 250;
+
+/// The most sub-steps a single physics step is split into.
+///
+/// Absurdly fast balls could otherwise stall the step,
+/// at the cost of skipping over some cells.
+const MAX_SUB_STEPS: Coordinate = 1024.0;
+
 /// Position or dimensions of some object on the screen within the coordinate space.
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Position {
     /// `x` coordinate of the position.
     pub x: Coordinate,
@@ -31,67 +53,343 @@ pub struct Position {
     pub y: Coordinate,
 }
 
-const INITIAL_SPEED: u8 = 100;
+impl Position {
+    /// Blend with the other position (`alpha` of `0.0` gives this one).
+    fn blend(&self, other: &Position, alpha: f32) -> Position {
+        Position {
+            x: self.x + (other.x - self.x) * alpha,
+            y: self.y + (other.y - self.y) * alpha,
+        }
+    }
+}
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 struct Movement {
-    /// Movement angle from 0 (right) to 359 clockwise.
-    angle: u16,
-    /// Speed of movement (see also [`INITIAL_SPEED`]).
+    /// Unit vector pointing in the direction of movement.
+    ///
+    /// Since `y` grows downwards, the angles are measured clockwise
+    /// with 0 being right.
+    direction: Position,
+    /// Speed of movement (coordinate units per millisecond).
     ///
-    /// The speed should be roughly between `INITIAL_SPEED` and `2 * INITIAL_SPEED`.
-    speed: u8,
+    /// The speed should be between [`GameConfig::initial_speed`]
+    /// and [`GameConfig::max_speed`].
+    speed: Coordinate,
 }
 
 impl Movement {
+    /// Create a movement heading at given angle (degrees, clockwise from right).
+    fn new(angle: Coordinate, speed: Coordinate) -> Self {
+        let (sin, cos) = angle.to_radians().sin_cos();
+        Self {
+            direction: Position { x: cos, y: sin },
+            speed,
+        }
+    }
+
+    /// Return the heading angle in degrees from 0 (right) to 360 clockwise.
+    #[cfg(test)]
+    fn angle(&self) -> Coordinate {
+        self.direction
+            .y
+            .atan2(self.direction.x)
+            .to_degrees()
+            .rem_euclid(360.0)
+    }
+
     /// Apply the movement to given position.
     ///
     /// The method will alter the next position the object is at.
     /// Note we do not take boundaries or other objects into account here,
     /// so the new position might be out of bounds.
     fn apply(&self, time_diff_ms: f32, position: &mut Position) {
-        let position_diff = (self.speed as f32 / INITIAL_SPEED as f32) * time_diff_ms / 2.0;
-
-        let angle = self.angle as f32;
-        let a_component = (self.angle % 90) as f32 / 90.0;
-        let b_component = 1.0 - a_component;
-
-        let quadrant = angle / 90.0;
-        let (x_component, y_component) = match quadrant {
-            q if q < 1.0 => (b_component, a_component),
-            q if q >= 1.0 && q < 2.0 => (-a_component, b_component),
-            q if q >= 2.0 && q < 3.0 => (-a_component, -b_component),
-            _ => (a_component, -b_component),
-        };
+        let position_diff = self.distance(time_diff_ms);
+
+        position.x += position_diff * self.direction.x;
+        position.y += position_diff * self.direction.y;
+    }
 
-        position.x += position_diff * x_component;
-        position.y += position_diff * y_component;
+    /// Distance travelled within given time.
+    fn distance(&self, time_diff_ms: f32) -> Coordinate {
+        self.speed * time_diff_ms
     }
 
     /// Reflect the movement, after the object has hit some obstacle.
     ///
     /// The rebound angle is matching the approach angle, however
-    /// there is slight (deterministic) skew based on the speed of the object.
-    fn bounce(&mut self, collision_type: CollisionType) {
-        let speed_factor = self.speed as u16 * 3 / INITIAL_SPEED as u16;
-        self.angle = match collision_type {
-            CollisionType::Horizontal => (540 - self.angle + speed_factor) % 360,
-            CollisionType::Vertical => (360 - self.angle + speed_factor) % 360,
+    /// there is slight (deterministic) skew based on the speed of the object
+    /// and optional (pseudo-random) jitter.
+    /// Every bounce speeds the object up a little bit.
+    /// The objects deflected by a paddle are sent exactly where the paddle aimed them.
+    fn bounce(&mut self, collision_type: CollisionType, config: &GameConfig, rng: &mut Rng) {
+        match collision_type {
+            CollisionType::Horizontal => self.direction.x = -self.direction.x,
+            CollisionType::Vertical => self.direction.y = -self.direction.y,
+            CollisionType::Both => {
+                self.direction.x = -self.direction.x;
+                self.direction.y = -self.direction.y;
+            }
+            CollisionType::Corner(normal) => {
+                let dot = self.direction.x * normal.x + self.direction.y * normal.y;
+                self.direction.x -= 2.0 * dot * normal.x;
+                self.direction.y -= 2.0 * dot * normal.y;
+            }
+            CollisionType::Deflected(direction) => {
+                self.direction = direction;
+                self.speed = (self.speed + config.speed_up()).min(config.max_speed());
+                return;
+            }
+        }
+        let skew = (config.angle_skew() * self.speed / config.initial_speed()).floor();
+        let jitter = if config.bounce_jitter() > 0.0 {
+            (rng.next_f32() * 2.0 - 1.0) * config.bounce_jitter()
+        } else {
+            0.0
+        };
+        self.rotate(skew + jitter);
+        self.speed = (self.speed + config.speed_up()).min(config.max_speed());
+    }
+
+    /// Bounce elastically off another object of the same mass.
+    ///
+    /// The objects exchange the components of their velocities along
+    /// the `normal` (a unit vector pointing from this object to the other one).
+    /// The resulting speeds are kept between the initial and the maximal speed,
+    /// so that the balls never stop.
+    /// Returns `false` (and changes nothing) if the objects are moving apart.
+    fn collide(&mut self, other: &mut Movement, normal: &Position, config: &GameConfig) -> bool {
+        let velocity = self.velocity();
+        let other_velocity = other.velocity();
+        let approach =
+            (velocity.x - other_velocity.x) * normal.x + (velocity.y - other_velocity.y) * normal.y;
+        if approach <= 0.0 {
+            return false;
+        }
+        let exchange = |velocity: Position, sign: Coordinate| Position {
+            x: velocity.x + sign * approach * normal.x,
+            y: velocity.y + sign * approach * normal.y,
+        };
+        self.set_velocity(exchange(velocity, -1.0), -1.0, normal, config);
+        other.set_velocity(exchange(other_velocity, 1.0), 1.0, normal, config);
+        true
+    }
+
+    fn velocity(&self) -> Position {
+        Position {
+            x: self.direction.x * self.speed,
+            y: self.direction.y * self.speed,
+        }
+    }
+
+    /// Set the velocity, falling back to the `normal` (scaled by `sign`)
+    /// if the velocity is zero.
+    fn set_velocity(
+        &mut self,
+        velocity: Position,
+        sign: Coordinate,
+        normal: &Position,
+        config: &GameConfig,
+    ) {
+        let speed = velocity.x.hypot(velocity.y);
+        self.direction = if speed > f32::EPSILON {
+            Position {
+                x: velocity.x / speed,
+                y: velocity.y / speed,
+            }
+        } else {
+            Position {
+                x: sign * normal.x,
+                y: sign * normal.y,
+            }
+        };
+        self.speed = speed.clamp(config.initial_speed(), config.max_speed());
+    }
+
+    /// Rotate the direction clockwise by given angle (degrees).
+    fn rotate(&mut self, angle: Coordinate) {
+        if angle == 0.0 {
+            return;
+        }
+        let (sin, cos) = angle.to_radians().sin_cos();
+        let Position { x, y } = self.direction;
+        let x2 = x * cos - y * sin;
+        let y2 = x * sin + y * cos;
+        // re-normalize to avoid accumulating rounding errors.
+        let length = x2.hypot(y2);
+        self.direction = Position {
+            x: x2 / length,
+            y: y2 / length,
         };
-        self.speed = (self.speed + 1).min(2 * INITIAL_SPEED);
     }
 }
 
+/// A ball flying over the board.
+///
+/// The ball belongs to a team and flips the cells of other teams it bounces off.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Ball {
+    /// The team the ball is playing for.
+    team: board::State,
+    /// Position after the last physics step.
+    position: Position,
+    /// Position before the last physics step (used for interpolation).
+    previous_position: Position,
+    movement: Movement,
+}
+
+impl Ball {
+    fn new(team: board::State, position: Position, movement: Movement) -> Self {
+        Self {
+            team,
+            previous_position: position.clone(),
+            position,
+            movement,
+        }
+    }
+
+    /// Return the team of the ball.
+    pub fn team(&self) -> board::State {
+        self.team
+    }
+
+    /// Get the position of the ball after the last physics step.
+    pub fn position(&self) -> &Position {
+        &self.position
+    }
+
+    /// Blend the previous and current position.
+    fn interpolate(&self, alpha: f32) -> Position {
+        self.previous_position.blend(&self.position, alpha)
+    }
+}
+
+/// A paddle steered by a player along a horizontal line.
+///
+/// Every team may have a single paddle (see [`Game::add_paddle`]),
+/// which follows its target (see [`Game::set_paddle_target`]) at a constant speed.
+/// All the balls bounce off the paddles, the rebound angle depends
+/// on where the ball hits the paddle
+/// (see [`crate::config::GameConfigBuilder::paddle_max_angle`]).
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Paddle {
+    /// The team steering the paddle.
+    team: board::State,
+    /// Center of the paddle after the last physics step.
+    position: Position,
+    /// Center of the paddle before the last physics step (used for interpolation).
+    previous_position: Position,
+    /// The `x` coordinate the paddle is heading to.
+    target: Coordinate,
+}
+
+impl Paddle {
+    fn new(team: board::State, position: Position) -> Self {
+        Self {
+            team,
+            previous_position: position.clone(),
+            target: position.x,
+            position,
+        }
+    }
+
+    /// Return the team of the paddle.
+    pub fn team(&self) -> board::State {
+        self.team
+    }
+
+    /// Get the center of the paddle after the last physics step.
+    pub fn position(&self) -> &Position {
+        &self.position
+    }
+
+    /// Get the `x` coordinate the paddle is heading to.
+    pub fn target(&self) -> Coordinate {
+        self.target
+    }
+
+    /// Move towards the target by at most given distance.
+    ///
+    /// The paddle never leaves the viewport.
+    fn advance(&mut self, distance: Coordinate, width: Coordinate, viewport_width: Coordinate) {
+        self.previous_position = self.position.clone();
+        let x = self.position.x + (self.target - self.position.x).clamp(-distance, distance);
+        self.position.x = x.min(viewport_width - width / 2.0).max(width / 2.0);
+    }
+
+    /// Blend the previous and current position.
+    fn interpolate(&self, alpha: f32) -> Position {
+        self.previous_position.blend(&self.position, alpha)
+    }
+}
+
+/// A plain copy of the observable game state (see [`Game::state`]).
+///
+/// Unlike the snapshots, the state is meant to be inspected
+/// (e.g. shipped as JSON with the `serde` feature), not to resume the game.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GameState {
+    /// Time of the last tick (milliseconds).
+    pub time: Timestamp,
+    /// Game time consumed by the physics steps (milliseconds).
+    pub elapsed: Timestamp,
+    /// Current status of the game.
+    pub status: GameStatus,
+    /// The board cells.
+    pub board: Board,
+    /// All the balls in the game.
+    pub balls: Vec<Ball>,
+    /// All the paddles in the game.
+    pub paddles: Vec<Paddle>,
+    /// Scores of all the teams, the leading team first.
+    pub scores: Vec<Score>,
+}
+
 /// Main game object encapsulating all parts of the game.
+///
+/// The physics is advanced in fixed steps (see [`GameConfig::step_ms`]),
+/// independently from how often [`Game::tick`] is called.
+/// The time that did not add up to a full step is carried over
+/// to the next tick, and can be used to interpolate the positions
+/// of objects for rendering (see [`Game::alpha`]).
+///
+/// Everything that happens during the physics steps is recorded
+/// as [`Event`]s, which should be regularly collected with [`Game::drain_events`].
 #[derive(Debug)]
 pub struct Game {
     board: Board,
     viewport_size: Position,
+    config: GameConfig,
     time: Timestamp,
+    accumulator: Timestamp,
     cell_size: Position,
     ball_radius: Coordinate,
-    lit_ball: (Position, Movement),
-    dark_ball: (Position, Movement),
+    balls: Vec<Ball>,
+    paddles: Vec<Paddle>,
+    rng: Rng,
+    events: Vec<Event>,
+    /// Game time consumed by the physics steps.
+    elapsed: Timestamp,
+    /// Number of cells flipped by every team.
+    flips: [u32; MAX_TEAMS as usize],
+    status: GameStatus,
+    /// Past states keyed by the elapsed game time.
+    history: History<Frame>,
+}
+
+/// A copy of the game state kept to rewind the game.
+#[derive(Debug, Clone)]
+struct Frame {
+    accumulator: Timestamp,
+    board: Board,
+    balls: Vec<Ball>,
+    paddles: Vec<Paddle>,
+    rng: Rng,
+    flips: [u32; MAX_TEAMS as usize],
+    status: GameStatus,
 }
 
 impl Game {
@@ -100,280 +398,2411 @@ impl Game {
         &self.board
     }
 
-    /// Get the position of the lit ball.
-    pub fn lit_ball(&self) -> &Position {
-        &self.lit_ball.0
-    }
-
-    /// Get the position of the dark ball.
-    pub fn dark_ball(&self) -> &Position {
-        &self.dark_ball.0
+    /// Take the board cells changed since the last call (see [`Board::take_dirty`]).
+    pub fn take_dirty(&mut self) -> Vec<(board::Index, board::Index)> {
+        self.board.take_dirty()
     }
 
-    /// Get the board cell size in coordinate space.
-    pub fn cell_size(&self) -> &Position {
-        &self.cell_size
+    /// View all the balls in the game.
+    ///
+    /// The index of the ball within the slice identifies it
+    /// in the other methods.
+    pub fn balls(&self) -> &[Ball] {
+        &self.balls
     }
 
-    /// Get the ball radius.
-    pub fn ball_radius(&self) -> Coordinate {
-        self.ball_radius
+    /// Get the ball at given index.
+    pub fn ball(&self, index: usize) -> Option<&Ball> {
+        self.balls.get(index)
     }
 
-    /// Create new game object.
+    /// All the positions the ball at given position has to be drawn at.
     ///
-    /// Given coordinate space dimensions (viewport size), the underlying
-    /// board and the starting time in milliseconds.
-    pub fn new(board: Board, start_time_ms: Timestamp, viewport_size: Position) -> Self {
-        let half_view = Position {
-            x: viewport_size.x / 2.0,
-            y: viewport_size.y / 2.0,
-        };
-        let y = half_view.y;
-
-        let init_pos_dark = Position {
-            x: half_view.x / 2.0 + half_view.x,
-            y,
+    /// Usually it's just the position itself, however with the wrapped edges
+    /// (see [`Boundaries::Wrap`]) the ball straddling an edge of the viewport
+    /// shows up at the opposite edge as well (in the corners up to four times).
+    pub fn ball_images(&self, position: &Position) -> Vec<Position> {
+        let mut images = vec![position.clone()];
+        if self.config.boundaries() != Boundaries::Wrap {
+            return images;
+        }
+        let shift = |coordinate: Coordinate, size: Coordinate| {
+            if coordinate < self.ball_radius {
+                Some(size)
+            } else if coordinate > size - self.ball_radius {
+                Some(-size)
+            } else {
+                None
+            }
         };
-        let init_pos_lit = Position {
-            x: half_view.x / 2.0,
-            y,
+        let shift_x = shift(position.x, self.viewport_size.x);
+        let shift_y = shift(position.y, self.viewport_size.y);
+        let image = |x: Coordinate, y: Coordinate| Position {
+            x: position.x + x,
+            y: position.y + y,
         };
+        if let Some(x) = shift_x {
+            images.push(image(x, 0.0));
+        }
+        if let Some(y) = shift_y {
+            images.push(image(0.0, y));
+        }
+        if let (Some(x), Some(y)) = (shift_x, shift_y) {
+            images.push(image(x, y));
+        }
+        images
+    }
 
-        let movement_dark = Movement {
-            angle: 220,
-            speed: INITIAL_SPEED,
-        };
+    /// Get the position of the ball at given index interpolated between physics steps.
+    pub fn interpolated_ball(&self, index: usize) -> Option<Position> {
+        let alpha = self.alpha();
+        self.balls.get(index).map(|ball| ball.interpolate(alpha))
+    }
 
-        let movement_lit = Movement {
-            angle: 40,
-            speed: INITIAL_SPEED,
-        };
+    /// Iterate over the teams and interpolated positions of all the balls.
+    pub fn interpolated_balls(&self) -> impl Iterator<Item = (board::State, Position)> + '_ {
+        let alpha = self.alpha();
+        self.balls
+            .iter()
+            .map(move |ball| (ball.team, ball.interpolate(alpha)))
+    }
 
-        let cell_size = Position {
-            x: viewport_size.x / board.size() as Coordinate,
-            y: viewport_size.y / board.size() as Coordinate,
-        };
+    /// Add a new ball playing for given team.
+    ///
+    /// The ball starts at given position, heading at given angle
+    /// (degrees, clockwise from right) with the configured initial speed.
+    /// Returns the index of the new ball.
+    /// Panics if the team is not playing on the board (see [`Game::try_add_ball`]).
+    pub fn add_ball(&mut self, team: board::State, position: Position, angle: Coordinate) -> usize {
+        self.try_add_ball(team, position, angle).or_panic()
+    }
 
-        assert!(
-            cell_size.x > 1.0,
-            "The viewport size is too small to draw a cell"
-        );
-        assert!(
-            cell_size.y > 1.0,
-            "The viewport size is too small to draw a cell"
-        );
+    /// Add a new ball playing for given team.
+    ///
+    /// Returns an error if the team is not playing on the board.
+    pub fn try_add_ball(
+        &mut self,
+        team: board::State,
+        position: Position,
+        angle: Coordinate,
+    ) -> Result<usize, Error> {
+        if team.id() >= self.board.teams() {
+            return Err(Error::UnknownTeam(team.id()));
+        }
+        let movement = Movement::new(angle, self.config.initial_speed());
+        self.balls.push(Ball::new(team, position, movement));
+        Ok(self.balls.len() - 1)
+    }
 
-        let ball_radius = (cell_size.x + cell_size.y) / 4.0;
+    /// Remove the ball at given index.
+    ///
+    /// Note the indices of all subsequent balls are shifted by one.
+    /// Returns `None` if there is no such ball.
+    pub fn remove_ball(&mut self, index: usize) -> Option<Ball> {
+        (index < self.balls.len()).then(|| self.balls.remove(index))
+    }
 
-        Self {
-            board,
-            time: start_time_ms,
-            viewport_size,
-            cell_size,
-            ball_radius,
-            lit_ball: (init_pos_lit, movement_lit),
-            dark_ball: (init_pos_dark, movement_dark),
-        }
+    /// View all the paddles in the game.
+    pub fn paddles(&self) -> &[Paddle] {
+        &self.paddles
     }
 
-    /// Recalculate objects positions and check collisions.
-    pub fn tick(&mut self, time_ms: Timestamp) {
-        assert!(time_ms > self.time, "The time did not change!");
-        let time_diff_ms = (time_ms - self.time) as f32;
-        self.time = time_ms;
+    /// Get the paddle of given team.
+    pub fn paddle(&self, team: board::State) -> Option<&Paddle> {
+        self.paddles.iter().find(|paddle| paddle.team == team)
+    }
 
-        for (obj, kind) in [
-            (&mut self.lit_ball, board::State::Lit),
-            (&mut self.dark_ball, board::State::Dark),
-        ] {
-            // 1. move objects
-            let (position, movement) = obj;
-            movement.apply(time_diff_ms, position);
-
-            // 2. check collisions:
-            //  2.2. With boundaries
-            //      2.2.1 bounce balls
-            Collisions::boundaries(position, movement, self.ball_radius, &self.viewport_size);
-            //  2.1. With board items:
-            //      2.1.1. flip board elements
-            //      2.1.2. bounce balls
-            Collisions::board(
-                position,
-                movement,
-                self.ball_radius,
-                &self.cell_size,
-                &mut self.board,
-                kind,
-            );
+    /// Get the size of the paddles (see [`GameConfig::paddle_width`]).
+    pub fn paddle_size(&self) -> Position {
+        Position {
+            x: self.config.paddle_width() * self.cell_size.x,
+            y: self.cell_size.y / 2.0,
         }
     }
-}
 
-struct Collisions;
+    /// Iterate over the teams and interpolated centers of all the paddles.
+    pub fn interpolated_paddles(&self) -> impl Iterator<Item = (board::State, Position)> + '_ {
+        let alpha = self.alpha();
+        self.paddles
+            .iter()
+            .map(move |paddle| (paddle.team, paddle.interpolate(alpha)))
+    }
 
-impl Collisions {
-    fn boundaries(
-        position: &mut Position,
-        movement: &mut Movement,
-        ball_radius: Coordinate,
-        viewport_size: &Position,
-    ) {
-        let mut collision_type = None;
-        // check collisions with the environment.
-        if position.x < ball_radius {
-            position.x = ball_radius;
-            collision_type = Some(CollisionType::Horizontal);
-        }
-        if position.x >= viewport_size.x - ball_radius {
-            position.x = viewport_size.x - ball_radius - 1.0;
-            collision_type = Some(CollisionType::Horizontal);
-        }
-        if position.y < ball_radius {
-            position.y = ball_radius;
-            collision_type = Some(CollisionType::Vertical);
+    /// Give the team a paddle centered at given position.
+    ///
+    /// Panics if the team is not playing on the board (see [`Game::try_add_paddle`]).
+    pub fn add_paddle(&mut self, team: board::State, position: Position) {
+        self.try_add_paddle(team, position).or_panic()
+    }
+
+    /// Give the team a paddle centered at given position.
+    ///
+    /// The paddle moves only horizontally, it stays at the `y` coordinate forever.
+    /// The existing paddle of the team is replaced.
+    /// Returns an error if the team is not playing on the board.
+    pub fn try_add_paddle(&mut self, team: board::State, position: Position) -> Result<(), Error> {
+        if team.id() >= self.board.teams() {
+            return Err(Error::UnknownTeam(team.id()));
         }
-        if position.y >= viewport_size.y - ball_radius {
-            position.y = viewport_size.y - ball_radius - 1.0;
-            collision_type = Some(CollisionType::Vertical);
+        let paddle = Paddle::new(team, position);
+        match self.paddles.iter_mut().find(|paddle| paddle.team == team) {
+            Some(existing) => *existing = paddle,
+            None => self.paddles.push(paddle),
         }
+        Ok(())
+    }
 
-        // bounce only once!
-        if let Some(collision_type) = collision_type {
-            movement.bounce(collision_type);
-        }
+    /// Remove the paddle of given team.
+    ///
+    /// Returns `None` if the team has no paddle.
+    pub fn remove_paddle(&mut self, team: board::State) -> Option<Paddle> {
+        let index = self.paddles.iter().position(|paddle| paddle.team == team)?;
+        Some(self.paddles.remove(index))
     }
 
-    fn board(
-        position: &mut Position,
-        movement: &mut Movement,
-        ball_radius: Coordinate,
-        cell_size: &Position,
-        board: &mut Board,
-        kind: board::State,
-    ) {
-        let mut collision_type = None;
-        for box_x in [position.x + ball_radius, position.x - ball_radius] {
-            for box_y in [position.y + ball_radius, position.y - ball_radius] {
-                let cell_x = (box_x / cell_size.x).floor() as board::Index;
-                let cell_y = (box_y / cell_size.y).floor() as board::Index;
-
-                let at_kind = board.cell(cell_y, cell_x);
-                if kind != at_kind {
-                    // check if it's actually colliding
-                    let cell_center_x = (cell_x as f32 + 0.5) * cell_size.x;
-                    let cell_center_y = (cell_y as f32 + 0.5) * cell_size.y;
-
-                    let distance_x = (cell_center_x - position.x).abs();
-                    let distance_y = (cell_center_y - position.y).abs();
-
-                    let distance_sq = distance_x * distance_x + distance_y * distance_y;
-                    let cell_size_avg = (cell_size.x + cell_size.y) / 4.0;
-                    let max_distance = cell_size_avg * 0.95 + ball_radius;
-                    let max_distance_sq = max_distance * max_distance;
-
-                    if distance_sq < max_distance_sq {
-                        // flip the cell
-                        board.flip(cell_y, cell_x);
-                        collision_type = if (cell_center_x - position.x).abs()
-                            < (cell_center_y - position.y).abs()
-                        {
-                            Some(CollisionType::Vertical)
-                        } else {
-                            Some(CollisionType::Horizontal)
-                        };
-                    }
-                }
-            }
-        }
-        if let Some(collision_type) = collision_type {
-            movement.bounce(collision_type);
-        }
+    /// Steer the paddle of given team towards given `x` coordinate.
+    ///
+    /// Panics if the team has no paddle (see [`Game::try_set_paddle_target`]).
+    pub fn set_paddle_target(&mut self, team: board::State, x: Coordinate) {
+        self.try_set_paddle_target(team, x).or_panic()
     }
-}
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-enum CollisionType {
-    Horizontal,
-    Vertical,
-}
+    /// Steer the paddle of given team towards given `x` coordinate.
+    ///
+    /// The paddle moves with the configured speed (see [`GameConfig::paddle_speed`])
+    /// during the following physics steps and stops at the target
+    /// (or at the edge of the viewport).
+    /// Returns an error if the team has no paddle.
+    pub fn try_set_paddle_target(
+        &mut self,
+        team: board::State,
+        x: Coordinate,
+    ) -> Result<(), Error> {
+        let paddle = self
+            .paddles
+            .iter_mut()
+            .find(|paddle| paddle.team == team)
+            .ok_or(Error::NoPaddle(team.id()))?;
+        paddle.target = x;
+        Ok(())
+    }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    /// View the events emitted since they were last drained.
+    pub fn events(&self) -> &[Event] {
+        &self.events
+    }
 
-    #[test]
-    fn should_calculate_horizontal_bounce_angle_correctly() {
-        let values = vec![
-            (0, 180),
-            (15, 165),
-            (30, 150),
-            (65, 115),
-            (89, 91),
-            (90, 90),   //edge case?
-            (270, 270), //edge case?
-            (105, 75),
-        ];
+    /// Take all the events emitted since they were last drained.
+    ///
+    /// The events are returned in the order they happened.
+    /// Note the events are queued until drained, so the queue grows
+    /// indefinitely if nobody is collecting them.
+    pub fn drain_events(&mut self) -> impl Iterator<Item = Event> + '_ {
+        self.events.drain(..)
+    }
 
-        for (angle, expected) in values {
-            let mut mov = Movement { angle, speed: 0 };
-            // when
-            mov.bounce(CollisionType::Horizontal);
+    /// Return the current status of the game.
+    ///
+    /// The game is finished once one of the end conditions configured
+    /// in [`GameConfig`] is met. The physics is not advanced afterwards.
+    pub fn status(&self) -> GameStatus {
+        self.status
+    }
 
-            // then
-            assert_eq!(mov.angle, expected);
+    /// Game time consumed by the physics steps (milliseconds).
+    pub fn elapsed(&self) -> Timestamp {
+        self.elapsed
+    }
+
+    /// Copy the current game state.
+    pub fn state(&self) -> GameState {
+        GameState {
+            time: self.time,
+            elapsed: self.elapsed,
+            status: self.status,
+            board: self.board.clone(),
+            balls: self.balls.clone(),
+            paddles: self.paddles.clone(),
+            scores: self.scoreboard(),
         }
     }
 
-    #[test]
-    fn should_calculate_vertical_bounce_angle_correctly() {
-        let values = vec![
-            (90, 270),
-            (0, 0),     //edge case?
-            (180, 180), //edge case?,
-            (120, 240),
-            (280, 80),
-        ];
+    /// Return the scores of all the teams.
+    ///
+    /// The teams owning the most cells come first,
+    /// ties are resolved by the number of flips.
+    pub fn scoreboard(&self) -> Vec<Score> {
+        let mut scores: Vec<_> = board::State::all(self.board.teams())
+            .map(|team| Score::new(team, self.board.count(team), self.flips[team.id() as usize]))
+            .collect();
+        scores.sort_by_key(|score| (Reverse(score.cells()), Reverse(score.flips()), score.team()));
+        scores
+    }
 
-        for (angle, expected) in values {
-            let mut mov = Movement { angle, speed: 0 };
-            // when
-            mov.bounce(CollisionType::Vertical);
+    /// Fraction of the physics step that is left over after the last tick.
+    ///
+    /// The value is between `0.0` (inclusive) and `1.0` (exclusive).
+    /// Interpolated positions are blended between the last two physics steps,
+    /// hence they lag behind the simulation by at most one step.
+    pub fn alpha(&self) -> f32 {
+        self.accumulator as f32 / self.config.step_ms() as f32
+    }
 
-            // then
-            assert_eq!(mov.angle, expected);
-        }
+    /// Get the game configuration.
+    pub fn config(&self) -> &GameConfig {
+        &self.config
     }
 
-    #[test]
-    fn should_not_find_collisions() {
-        let kind = board::State::Lit;
-        let ball_radius = 10f32;
+    /// Get the board cell size in coordinate space.
+    pub fn cell_size(&self) -> &Position {
+        &self.cell_size
+    }
 
-        let cell_size = Position { x: 10.0, y: 10.0 };
-        let mut position = Position { x: 10.0, y: 15.0 };
+    /// Get the ball radius.
+    pub fn ball_radius(&self) -> Coordinate {
+        self.ball_radius
+    }
 
-        let mut movement = Movement {
-            angle: 90,
-            speed: 1,
+    /// Compute board dimensions that give square-ish cells for given viewport.
+    ///
+    /// The shorter side of the viewport is covered by `cells` cells and
+    /// the longer side gets as many cells as needed to keep the aspect ratio.
+    /// The result can be passed directly to [`Board::new`].
+    /// This is the only way to get square cells, since [`Game::new`]
+    /// always stretches the board over the entire viewport.
+    pub fn board_size_for(
+        viewport_size: &Position,
+        cells: board::Index,
+    ) -> (board::Index, board::Index) {
+        let shorter = viewport_size.x.min(viewport_size.y);
+        let cell_size = shorter / cells as Coordinate;
+        let along = |length: Coordinate| {
+            let count = (length / cell_size).round();
+            count.clamp(cells as Coordinate, board::Index::MAX as Coordinate) as board::Index
         };
-        let mut board = Board::new(5);
+        (along(viewport_size.x), along(viewport_size.y))
+    }
 
-        // we are only touching Lit board cells,
-        // so there should be no collisions.
-        // However bounding rectangle is touching cells at (2,0) and (2,2)
-        // hence we are testing if these collisions are omitted.
-        Collisions::board(
-            &mut position,
-            &mut movement,
+    /// Create new game object.
+    ///
+    /// Given coordinate space dimensions (viewport size), the underlying
+    /// board, the starting time in milliseconds and the gameplay configuration.
+    /// The game starts with a single ball for every team on the board,
+    /// placed in the middle of the team's initial stripe (e.g. a lit ball
+    /// in the left half and a dark ball in the right half of the viewport).
+    ///
+    /// The board is stretched over the entire viewport (there is no letterboxing),
+    /// so the cells are square only if the board and viewport aspect ratios match.
+    /// Callers that need square cells have to size the board for the viewport
+    /// with [`Game::board_size_for`], otherwise the cells (and the ball radius
+    /// derived from them) are distorted along the longer side.
+    ///
+    /// Panics if the viewport is too small (see [`Game::try_new`]).
+    pub fn new(
+        board: Board,
+        start_time_ms: Timestamp,
+        viewport_size: Position,
+        config: GameConfig,
+    ) -> Self {
+        Self::try_new(board, start_time_ms, viewport_size, config).or_panic()
+    }
+
+    /// Create new game object.
+    ///
+    /// Returns an error if the viewport is too small to draw
+    /// a cell of at least one coordinate unit.
+    pub fn try_new(
+        board: Board,
+        start_time_ms: Timestamp,
+        viewport_size: Position,
+        config: GameConfig,
+    ) -> Result<Self, Error> {
+        let (cell_size, ball_radius) = Self::sizes(&board, &viewport_size, &config)?;
+        let mut rng = Rng::new(config.seed().unwrap_or_default());
+        let mut balls = Self::initial_balls(&board, &viewport_size, &cell_size, &config);
+        if config.seed().is_some() {
+            Self::randomize_balls(&mut balls, &board, &cell_size, &mut rng);
+        }
+
+        let history = History::new(config.rewind_capacity());
+        let mut game = Self {
+            config,
+            time: start_time_ms,
+            accumulator: 0,
+            viewport_size,
+            cell_size,
             ball_radius,
-            &cell_size,
-            &mut board,
-            kind,
-        );
+            balls,
+            paddles: Vec::new(),
+            board,
+            rng,
+            events: Vec::new(),
+            elapsed: 0,
+            flips: [0; MAX_TEAMS as usize],
+            status: GameStatus::Running,
+            history,
+        };
+        game.record_frame();
+        Ok(game)
+    }
 
-        // no change
-        assert_eq!(movement.angle, 90);
-        assert_eq!(movement.speed, 1);
+    /// Compute the cell size and the ball radius for given viewport.
+    fn sizes(
+        board: &Board,
+        viewport_size: &Position,
+        config: &GameConfig,
+    ) -> Result<(Position, Coordinate), Error> {
+        let cell_size = Position {
+            x: viewport_size.x / board.width() as Coordinate,
+            y: viewport_size.y / board.height() as Coordinate,
+        };
+
+        // note the negated comparison rejects `NaN` sizes as well.
+        if !(cell_size.x > 1.0 && cell_size.y > 1.0) {
+            return Err(Error::ViewportTooSmall);
+        }
+
+        let ball_radius = config.ball_radius_ratio() * (cell_size.x + cell_size.y) / 2.0;
+        Ok((cell_size, ball_radius))
+    }
+
+    /// Save the full game state into a binary snapshot.
+    ///
+    /// The snapshot contains the board, the balls, the time and the configuration,
+    /// but not the pending events nor the rewind history. See [`snapshot`] for the layout details.
+    pub fn snapshot(&self) -> Vec<u8> {
+        snapshot::seal(snapshot::MAGIC, |writer| self.encode(writer))
+    }
+
+    /// Resume the game from a snapshot created by [`Game::snapshot`].
+    ///
+    /// Returns an error if the snapshot is damaged or was created
+    /// by an incompatible version of the engine.
+    pub fn restore(bytes: &[u8]) -> Result<Self, Error> {
+        snapshot::open(snapshot::MAGIC, bytes, Self::decode)
+    }
+
+    /// Compute a hash of the full game state.
+    ///
+    /// Two games with the same hash are (almost certainly) identical,
+    /// which is used to verify the replays (see [`crate::replay`]).
+    pub fn state_hash(&self) -> u64 {
+        snapshot::hash(|writer| self.encode(writer))
+    }
+
+    /// Continue the game clock from given time.
+    ///
+    /// The snapshot keeps the time of the last tick, so a game restored
+    /// in a new session has to be told the current time, otherwise
+    /// the next tick would simulate the whole gap between the sessions.
+    pub fn rebase_time(&mut self, time_ms: Timestamp) {
+        self.time = time_ms;
+    }
+
+    /// Place a ball in the middle of every team initial stripe.
+    ///
+    /// See [`Board::with_teams`] for how the stripes are laid out.
+    /// In case the board has a different layout, the ball is moved
+    /// to the closest cell owned by its team.
+    fn initial_balls(
+        board: &Board,
+        viewport_size: &Position,
+        cell_size: &Position,
+        config: &GameConfig,
+    ) -> Vec<Ball> {
+        let is_wide = board.width() >= board.height();
+        let stripes = board.teams() as Coordinate;
+        let teams = [board::State::LIT, board::State::DARK]
+            .into_iter()
+            .chain((2..board.teams()).map(board::State::team));
+
+        teams
+            .enumerate()
+            .map(|(stripe, team)| {
+                let along = (2.0 * stripe as Coordinate + 1.0) / (2.0 * stripes);
+                let (x, y) = if is_wide { (along, 0.5) } else { (0.5, along) };
+                let position = Position {
+                    x: x * viewport_size.x,
+                    y: y * viewport_size.y,
+                };
+                let position = Self::closest_own_cell(board, cell_size, team, position);
+                let angle = config
+                    .initial_angle(team)
+                    .expect("the teams on the board are below MAX_TEAMS");
+                let movement = Movement::new(angle, config.initial_speed());
+                Ball::new(team, position, movement)
+            })
+            .collect()
+    }
+
+    /// Return the center of the cell owned by the team closest to the position.
+    ///
+    /// The position is returned as is if it's already within such a cell
+    /// or the team does not own any cells.
+    fn closest_own_cell(
+        board: &Board,
+        cell_size: &Position,
+        team: board::State,
+        position: Position,
+    ) -> Position {
+        let cell_of = |coordinate: Coordinate, size: Coordinate, count: board::Index| {
+            ((coordinate / size) as board::Index).min(count - 1)
+        };
+        let row = cell_of(position.y, cell_size.y, board.height());
+        let col = cell_of(position.x, cell_size.x, board.width());
+        if board.cell(row, col) == team {
+            return position;
+        }
+
+        let center = |row: board::Index, col: board::Index| Position {
+            x: (col as Coordinate + 0.5) * cell_size.x,
+            y: (row as Coordinate + 0.5) * cell_size.y,
+        };
+        let distance =
+            |other: &Position| (other.x - position.x).powi(2) + (other.y - position.y).powi(2);
+        (0..board.height())
+            .flat_map(|row| (0..board.width()).map(move |col| (row, col)))
+            .filter(|(row, col)| board.cell(*row, *col) == team)
+            .map(|(row, col)| center(row, col))
+            .min_by(|a, b| distance(a).total_cmp(&distance(b)))
+            .unwrap_or(position)
+    }
+
+    /// Move the balls to random cells of their teams and launch them at random angles.
+    fn randomize_balls(balls: &mut [Ball], board: &Board, cell_size: &Position, rng: &mut Rng) {
+        // give up after a while, in case the team has (almost) no cells.
+        const ATTEMPTS: usize = 64;
+        for ball in balls {
+            for _ in 0..ATTEMPTS {
+                let row = rng.below(board.height() as u64) as board::Index;
+                let col = rng.below(board.width() as u64) as board::Index;
+                if board.cell(row, col) == ball.team {
+                    ball.position = Position {
+                        x: (col as Coordinate + 0.5) * cell_size.x,
+                        y: (row as Coordinate + 0.5) * cell_size.y,
+                    };
+                    ball.previous_position = ball.position.clone();
+                    break;
+                }
+            }
+            let angle = rng.next_f32() * 360.0;
+            ball.movement = Movement::new(angle, ball.movement.speed);
+        }
+    }
+
+    /// Recalculate objects positions and check collisions.
+    ///
+    /// The elapsed time is consumed in as many fixed physics steps as possible,
+    /// the remainder is carried over to the next tick.
+    /// At most [`MAX_STEPS_PER_TICK`] steps are run, the time beyond them is dropped.
+    ///
+    /// Panics if the time did not move forward (see [`Game::try_tick`]).
+    pub fn tick(&mut self, time_ms: Timestamp) {
+        self.try_tick(time_ms).or_panic()
+    }
+
+    /// Recalculate objects positions and check collisions.
+    ///
+    /// Returns an error if the time did not move forward since the previous tick.
+    pub fn try_tick(&mut self, time_ms: Timestamp) -> Result<(), Error> {
+        if time_ms <= self.time {
+            return Err(Error::TimeNotAdvanced {
+                previous: self.time,
+                current: time_ms,
+            });
+        }
+        let time_diff = time_ms - self.time;
+        self.time = time_ms;
+        if self.status.is_finished() {
+            return Ok(());
+        }
+        let step_ms = self.config.step_ms();
+        self.accumulator = self
+            .accumulator
+            .saturating_add(time_diff)
+            .min(MAX_STEPS_PER_TICK * step_ms);
+
+        while self.accumulator >= step_ms {
+            self.accumulator -= step_ms;
+            let absorbed = self.step();
+            self.status = self.check_status(absorbed);
+            if let GameStatus::Finished { winner } = self.status {
+                self.finish(winner);
+                break;
+            }
+        }
+        self.record_frame();
+        Ok(())
+    }
+
+    /// Rewind the game to the latest state recorded at or before given game time.
+    ///
+    /// The time is the game time (see [`Game::elapsed`]), not the time passed
+    /// to the ticks, which keeps going forward as usual.
+    /// The board, the balls and the scores are restored exactly,
+    /// the states recorded after that time are dropped.
+    ///
+    /// Returns an error if there is no such state, e.g. the history
+    /// is disabled (see [`crate::config::GameConfigBuilder::rewind_capacity`])
+    /// or the state is too old to be kept.
+    pub fn rewind_to(&mut self, elapsed_ms: Timestamp) -> Result<(), Error> {
+        let (elapsed, frame) = self
+            .history
+            .rewind_to(elapsed_ms)
+            .cloned()
+            .ok_or(Error::NotRecorded(elapsed_ms))?;
+        self.restore_frame(elapsed, frame);
+        Ok(())
+    }
+
+    /// Rewind the game by a single recorded state.
+    ///
+    /// Returns the game time of the restored state
+    /// or `None` if there is no earlier state recorded.
+    pub fn step_back(&mut self) -> Option<Timestamp> {
+        let (elapsed, frame) = self.history.step_back().cloned()?;
+        self.restore_frame(elapsed, frame);
+        Some(elapsed)
+    }
+
+    /// The range of the game time the game can be rewound to.
+    pub fn rewind_range(&self) -> Option<(Timestamp, Timestamp)> {
+        self.history.range()
+    }
+
+    fn record_frame(&mut self) {
+        if !self.history.is_enabled() {
+            return;
+        }
+        let frame = Frame {
+            accumulator: self.accumulator,
+            board: self.board.copy_cells(),
+            balls: self.balls.clone(),
+            paddles: self.paddles.clone(),
+            rng: self.rng.clone(),
+            flips: self.flips,
+            status: self.status,
+        };
+        self.history.push(self.elapsed, frame);
+    }
+
+    fn restore_frame(&mut self, elapsed: Timestamp, frame: Frame) {
+        let Frame {
+            accumulator,
+            board,
+            balls,
+            paddles,
+            rng,
+            flips,
+            status,
+        } = frame;
+        self.elapsed = elapsed;
+        self.accumulator = accumulator;
+        self.board.restore_from(&board);
+        self.balls = balls;
+        self.paddles = paddles;
+        self.rng = rng;
+        self.flips = flips;
+        self.status = status;
+    }
+
+    /// Check if any of the configured end conditions is met.
+    ///
+    /// The `absorbed` team has just lost a ball (see [`Boundaries::Absorb`]).
+    fn check_status(&self, absorbed: Option<board::State>) -> GameStatus {
+        let has_condition = absorbed.is_some()
+            || self.config.territory_threshold().is_some()
+            || self.config.flips_to_win().is_some()
+            || self.config.time_limit().is_some();
+        if !has_condition {
+            // Spare the scoreboard on every step of an endless game.
+            return GameStatus::Running;
+        }
+        let scores = self.scoreboard();
+        if let Some(absorbed) = absorbed {
+            return GameStatus::Finished {
+                winner: scores
+                    .iter()
+                    .map(|score| score.team())
+                    .find(|team| *team != absorbed),
+            };
+        }
+        if let Some(threshold) = self.config.territory_threshold() {
+            if let Some(score) = scores
+                .iter()
+                .find(|score| self.board.territory(score.team()) >= threshold)
+            {
+                return GameStatus::Finished {
+                    winner: Some(score.team()),
+                };
+            }
+        }
+        if let Some(flips) = self.config.flips_to_win() {
+            if let Some(score) = scores
+                .iter()
+                .filter(|score| score.flips() >= flips)
+                .max_by_key(|score| score.flips())
+            {
+                return GameStatus::Finished {
+                    winner: Some(score.team()),
+                };
+            }
+        }
+        if let Some(time_limit) = self.config.time_limit() {
+            if self.elapsed >= time_limit {
+                let is_draw = scores[0].cells() == scores[1].cells();
+                return GameStatus::Finished {
+                    winner: (!is_draw).then(|| scores[0].team()),
+                };
+            }
+        }
+        GameStatus::Running
+    }
+
+    /// Stop the balls and the paddles and announce the result.
+    fn finish(&mut self, winner: Option<board::State>) {
+        self.accumulator = 0;
+        for ball in &mut self.balls {
+            ball.previous_position = ball.position.clone();
+        }
+        for paddle in &mut self.paddles {
+            paddle.previous_position = paddle.position.clone();
+        }
+        self.events.push(Event::GameFinished { winner });
+    }
+
+    /// Advance the physics by a single fixed step.
+    ///
+    /// Returns the team of the first ball absorbed by the edge of the viewport.
+    fn step(&mut self) -> Option<board::State> {
+        let events = self.events.len();
+        self.elapsed += self.config.step_ms();
+        let time_diff_ms = self.config.step_ms() as f32;
+        // the paddles move first, so that the balls bounce off their latest positions.
+        let paddle_size = self.paddle_size();
+        let paddle_travel = self.config.paddle_speed() * time_diff_ms;
+        for paddle in &mut self.paddles {
+            paddle.advance(paddle_travel, paddle_size.x, self.viewport_size.x);
+        }
+        for ball in &mut self.balls {
+            ball.previous_position = ball.position.clone();
+        }
+        // The paths are walked in sub-steps short enough for the fastest ball
+        // not to skip over any cell, so that the collisions (including the ones
+        // between the balls) are resolved in the order they happen along the paths.
+        let max_travel = Collisions::max_travel(self.ball_radius, &self.cell_size);
+        let fastest = self
+            .balls
+            .iter()
+            .map(|ball| ball.movement.distance(time_diff_ms))
+            .fold(0.0, Coordinate::max);
+        let sub_steps = (fastest / max_travel).ceil().clamp(1.0, MAX_SUB_STEPS);
+        let sub_step_ms = time_diff_ms / sub_steps;
+        let mut absorbed = None;
+        // the start positions of the sub-steps, reused across them.
+        let mut starts = Vec::with_capacity(self.balls.len());
+        for _ in 0..sub_steps as usize {
+            starts.clear();
+            starts.extend(self.balls.iter().map(|b| b.position.clone()));
+            for index in 0..self.balls.len() {
+                absorbed = absorbed.or(self.advance_ball(index, sub_step_ms));
+            }
+            // 4. bounce balls off each other
+            absorbed = absorbed.or(self.collide_balls(&mut starts, sub_step_ms));
+            // the round is over, there is no point in moving the other balls.
+            if absorbed.is_some() {
+                break;
+            }
+        }
+
+        for event in &self.events[events..] {
+            if let Event::CellFlipped { by_team, .. } = event {
+                self.flips[by_team.id() as usize] += 1;
+            }
+        }
+        absorbed
+    }
+
+    /// Move the ball at given index for given time and resolve its collisions
+    /// with the edges of the viewport, the board and the paddles.
+    ///
+    /// The time has to be short enough for the ball not to skip over any cell.
+    /// Returns the team of the ball if it was absorbed by the edge.
+    fn advance_ball(&mut self, index: usize, time_ms: f32) -> Option<board::State> {
+        let paddle_size = self.paddle_size();
+        let grid = Grid {
+            cell_size: &self.cell_size,
+            wrap: self.config.boundaries() == Boundaries::Wrap,
+        };
+        let Ball {
+            team,
+            position,
+            previous_position,
+            movement,
+        } = &mut self.balls[index];
+        // 1. move objects
+        movement.apply(time_ms, position);
+
+        // 2. check collisions:
+        //  2.1. With boundaries
+        let wall = match Collisions::boundaries(
+            position,
+            previous_position,
+            &movement.direction,
+            self.ball_radius,
+            &self.viewport_size,
+            self.config.boundaries(),
+        ) {
+            Some(Boundary::Bounce(collision_type)) => Some(collision_type),
+            Some(Boundary::Absorbed) => {
+                self.events.push(Event::BallAbsorbed {
+                    ball: index,
+                    position: position.clone(),
+                });
+                return Some(*team);
+            }
+            None => None,
+        };
+        //  2.2. With board items (flips board elements, except walls)
+        let cell = Collisions::board(
+            position,
+            &movement.direction,
+            self.ball_radius,
+            &grid,
+            &mut self.board,
+            *team,
+            &mut self.events,
+        );
+        //  2.3. With paddles
+        let paddle = Collisions::paddles(
+            position,
+            &movement.direction,
+            self.ball_radius,
+            &self.paddles,
+            &paddle_size,
+            self.config.paddle_max_angle(),
+        );
+
+        // 3. bounce balls
+        let bounces = [
+            wall.map(|collision_type| (collision_type, BounceKind::Wall)),
+            cell,
+            paddle.map(|collision_type| (collision_type, BounceKind::Paddle)),
+        ];
+        for (collision_type, kind) in bounces.into_iter().flatten() {
+            let speed = movement.speed;
+            movement.bounce(collision_type, &self.config, &mut self.rng);
+            self.events.push(Event::BallBounced {
+                ball: index,
+                kind,
+                position: position.clone(),
+            });
+            if movement.speed != speed {
+                self.events.push(Event::SpeedChanged {
+                    ball: index,
+                    speed: movement.speed,
+                });
+            }
+        }
+        None
+    }
+
+    /// Bounce the balls that met during the last sub-step off each other.
+    ///
+    /// The `starts` are the positions of the balls before the sub-step.
+    /// The balls are moved back to the point of contact, bounce there,
+    /// and continue with the new headings for the rest of the sub-step
+    /// (colliding with the edges, the board and the paddles as usual).
+    /// The overlapping balls are pushed apart, so that they don't get stuck.
+    /// Returns the team of the first ball absorbed by the edge.
+    fn collide_balls(&mut self, starts: &mut [Position], sub_step_ms: f32) -> Option<board::State> {
+        let mode = self.config.ball_collisions();
+        if mode == BallCollisions::Off {
+            return None;
+        }
+        let mut absorbed = None;
+        let min_distance = 2.0 * self.ball_radius;
+        // the paths of the balls that wrapped around start beyond the opposite edge.
+        let wrap = self.config.boundaries() == Boundaries::Wrap;
+        let viewport_size = self.viewport_size.clone();
+        let path_start = |start: &Position, end: &Position| {
+            let unwrap = |start: Coordinate, end: Coordinate, size: Coordinate| {
+                start + ((end - start) / size).round() * size
+            };
+            if !wrap {
+                return start.clone();
+            }
+            Position {
+                x: unwrap(start.x, end.x, viewport_size.x),
+                y: unwrap(start.y, end.y, viewport_size.y),
+            }
+        };
+        for index in 0..self.balls.len() {
+            for other_index in index + 1..self.balls.len() {
+                let (head, tail) = self.balls.split_at_mut(other_index);
+                let (ball, other) = (&mut head[index], &mut tail[0]);
+                if !mode.applies(ball.team, other.team) {
+                    continue;
+                }
+                let start = path_start(&starts[index], &ball.position);
+                let other_start = path_start(&starts[other_index], &other.position);
+                let path = (&start, &ball.position);
+                let other_path = (&other_start, &other.position);
+                let Some(time) = Collisions::balls(path, other_path, min_distance) else {
+                    continue;
+                };
+                let mut contact = start.blend(&ball.position, time);
+                let mut other_contact = other_start.blend(&other.position, time);
+                let distance_x = other_contact.x - contact.x;
+                let distance_y = other_contact.y - contact.y;
+                let distance = distance_x.hypot(distance_y);
+                if distance == 0.0 {
+                    continue;
+                }
+                let normal = Position {
+                    x: distance_x / distance,
+                    y: distance_y / distance,
+                };
+                let overlap = (min_distance - distance).max(0.0) / 2.0;
+                contact.x -= normal.x * overlap;
+                contact.y -= normal.y * overlap;
+                other_contact.x += normal.x * overlap;
+                other_contact.y += normal.y * overlap;
+
+                let speeds = [ball.movement.speed, other.movement.speed];
+                let collided = ball
+                    .movement
+                    .collide(&mut other.movement, &normal, &self.config);
+                if collided {
+                    self.events.push(Event::BallsCollided {
+                        ball: index,
+                        other: other_index,
+                        position: Position {
+                            x: contact.x + normal.x * self.ball_radius,
+                            y: contact.y + normal.y * self.ball_radius,
+                        },
+                    });
+                    let balls = [(index, &*ball), (other_index, &*other)];
+                    for ((index, ball), speed) in balls.into_iter().zip(speeds) {
+                        if ball.movement.speed != speed {
+                            self.events.push(Event::SpeedChanged {
+                                ball: index,
+                                speed: ball.movement.speed,
+                            });
+                        }
+                    }
+                }
+                ball.position = contact.clone();
+                other.position = other_contact.clone();
+                // the rest of the paths start at the point of contact.
+                starts[index] = contact;
+                starts[other_index] = other_contact;
+                let remaining_ms = (1.0 - time) * sub_step_ms;
+                for index in [index, other_index] {
+                    absorbed = absorbed.or(self.advance_ball(index, remaining_ms));
+                }
+            }
+        }
+        absorbed
+    }
+}
+
+impl Encode for Position {
+    fn encode(&self, writer: &mut Writer) {
+        self.x.encode(writer);
+        self.y.encode(writer);
+    }
+
+    fn decode(reader: &mut Reader) -> Result<Self, Error> {
+        Ok(Self {
+            x: Encode::decode(reader)?,
+            y: Encode::decode(reader)?,
+        })
+    }
+}
+
+impl Encode for Ball {
+    fn encode(&self, writer: &mut Writer) {
+        self.team.encode(writer);
+        self.position.encode(writer);
+        self.previous_position.encode(writer);
+        self.movement.direction.encode(writer);
+        self.movement.speed.encode(writer);
+    }
+
+    fn decode(reader: &mut Reader) -> Result<Self, Error> {
+        Ok(Self {
+            team: Encode::decode(reader)?,
+            position: Encode::decode(reader)?,
+            previous_position: Encode::decode(reader)?,
+            movement: Movement {
+                direction: Encode::decode(reader)?,
+                speed: Encode::decode(reader)?,
+            },
+        })
+    }
+}
+
+impl Encode for Paddle {
+    fn encode(&self, writer: &mut Writer) {
+        self.team.encode(writer);
+        self.position.encode(writer);
+        self.previous_position.encode(writer);
+        self.target.encode(writer);
+    }
+
+    fn decode(reader: &mut Reader) -> Result<Self, Error> {
+        Ok(Self {
+            team: Encode::decode(reader)?,
+            position: Encode::decode(reader)?,
+            previous_position: Encode::decode(reader)?,
+            target: Encode::decode(reader)?,
+        })
+    }
+}
+
+impl Encode for Game {
+    fn encode(&self, writer: &mut Writer) {
+        self.config.encode(writer);
+        self.board.encode(writer);
+        self.viewport_size.encode(writer);
+        self.time.encode(writer);
+        self.accumulator.encode(writer);
+        self.elapsed.encode(writer);
+        self.balls.encode(writer);
+        self.paddles.encode(writer);
+        self.rng.encode(writer);
+        self.flips.encode(writer);
+        self.status.encode(writer);
+    }
+
+    fn decode(reader: &mut Reader) -> Result<Self, Error> {
+        let config = GameConfig::decode(reader)?;
+        let board = Board::decode(reader)?;
+        let viewport_size = Position::decode(reader)?;
+        let (cell_size, ball_radius) = Self::sizes(&board, &viewport_size, &config)?;
+        let mut game = Self {
+            time: Encode::decode(reader)?,
+            accumulator: Encode::decode(reader)?,
+            elapsed: Encode::decode(reader)?,
+            balls: Encode::decode(reader)?,
+            paddles: Encode::decode(reader)?,
+            rng: Encode::decode(reader)?,
+            flips: Encode::decode(reader)?,
+            status: Encode::decode(reader)?,
+            events: Vec::new(),
+            history: History::new(config.rewind_capacity()),
+            config,
+            board,
+            viewport_size,
+            cell_size,
+            ball_radius,
+        };
+        if game.accumulator >= game.config.step_ms() {
+            return Err(Error::CorruptedSnapshot(
+                "Time accumulator exceeds the step",
+            ));
+        }
+        if let Some(ball) = game
+            .balls
+            .iter()
+            .find(|b| b.team.id() >= game.board.teams())
+        {
+            return Err(Error::UnknownTeam(ball.team.id()));
+        }
+        for (index, paddle) in game.paddles.iter().enumerate() {
+            if paddle.team.id() >= game.board.teams() {
+                return Err(Error::UnknownTeam(paddle.team.id()));
+            }
+            if game.paddles[..index].iter().any(|p| p.team == paddle.team) {
+                return Err(Error::CorruptedSnapshot("Multiple paddles of a team"));
+            }
+        }
+        game.record_frame();
+        Ok(game)
+    }
+}
+
+struct Collisions;
+
+impl Collisions {
+    /// Maximal distance the ball can travel between two collision checks.
+    ///
+    /// Half of the smallest obstacle dimension guarantees that
+    /// the ball overlaps every cell it passes through at least once.
+    fn max_travel(ball_radius: Coordinate, cell_size: &Position) -> Coordinate {
+        ball_radius.min(cell_size.x).min(cell_size.y) / 2.0
+    }
+
+    /// Keep the ball within the viewport.
+    ///
+    /// The edges are handled on both axes independently, so that hitting
+    /// a corner reverses both directions, and the ball is clamped to the same
+    /// distance (its radius) from the near and the far edges.
+    /// The edges the ball is moving away from don't bounce it.
+    /// Wrapping the ball around shifts the previous position as well,
+    /// so that the interpolation doesn't sweep across the viewport.
+    fn boundaries(
+        position: &mut Position,
+        previous_position: &mut Position,
+        direction: &Position,
+        ball_radius: Coordinate,
+        viewport_size: &Position,
+        boundaries: Boundaries,
+    ) -> Option<Boundary> {
+        let axis = |position: &mut Coordinate,
+                    previous: &mut Coordinate,
+                    direction: Coordinate,
+                    size: Coordinate| {
+            if boundaries == Boundaries::Wrap {
+                let shift = if *position < 0.0 {
+                    size
+                } else if *position >= size {
+                    -size
+                } else {
+                    0.0
+                };
+                *position += shift;
+                *previous += shift;
+                return false;
+            }
+            if *position < ball_radius {
+                *position = ball_radius;
+                direction < 0.0
+            } else if *position > size - ball_radius {
+                *position = size - ball_radius;
+                direction > 0.0
+            } else {
+                false
+            }
+        };
+        let horizontal = axis(
+            &mut position.x,
+            &mut previous_position.x,
+            direction.x,
+            viewport_size.x,
+        );
+        let vertical = axis(
+            &mut position.y,
+            &mut previous_position.y,
+            direction.y,
+            viewport_size.y,
+        );
+        let collision_type = match (horizontal, vertical) {
+            (true, true) => CollisionType::Both,
+            (true, false) => CollisionType::Horizontal,
+            (false, true) => CollisionType::Vertical,
+            (false, false) => return None,
+        };
+        Some(match boundaries {
+            Boundaries::Absorb => Boundary::Absorbed,
+            _ => Boundary::Bounce(collision_type),
+        })
+    }
+
+    /// Find the moment at which the balls moving along given paths touched.
+    ///
+    /// The paths are the straight lines from the start to the end positions
+    /// of the balls. Returns the fraction of the paths (`1.0` if the balls
+    /// are overlapping at the end) or `None` if they haven't met.
+    fn balls(
+        (start, end): (&Position, &Position),
+        (other_start, other_end): (&Position, &Position),
+        min_distance: Coordinate,
+    ) -> Option<Coordinate> {
+        let start_x = other_start.x - start.x;
+        let start_y = other_start.y - start.y;
+        let end_x = other_end.x - end.x;
+        let end_y = other_end.y - end.y;
+        // solve |start + t * (end - start)| = min_distance for the first t.
+        let (delta_x, delta_y) = (end_x - start_x, end_y - start_y);
+        let a = delta_x * delta_x + delta_y * delta_y;
+        let b = 2.0 * (start_x * delta_x + start_y * delta_y);
+        let c = start_x * start_x + start_y * start_y - min_distance * min_distance;
+        let discriminant = b * b - 4.0 * a * c;
+        if a > 0.0 && c >= 0.0 && discriminant >= 0.0 {
+            let time = (-b - discriminant.sqrt()) / (2.0 * a);
+            if (0.0..=1.0).contains(&time) {
+                return Some(time);
+            }
+        }
+        (end_x.hypot(end_y) < min_distance).then_some(1.0)
+    }
+
+    /// Find the deepest contact of the ball with the cells of other teams (or walls).
+    ///
+    /// The ball is pushed out of that cell along the contact normal,
+    /// so that it never stays embedded, and only that single cell is flipped.
+    /// The contacts the ball is already moving away from are ignored.
+    fn board(
+        position: &mut Position,
+        direction: &Position,
+        ball_radius: Coordinate,
+        grid: &Grid,
+        board: &mut Board,
+        kind: board::State,
+        events: &mut Vec<Event>,
+    ) -> Option<(CollisionType, BounceKind)> {
+        // the cells overlapped by the bounding box of the ball.
+        let rows = grid.cells(position.y, ball_radius, grid.cell_size.y, board.height());
+        let mut deepest: Option<(Contact, board::Index, board::Index)> = None;
+        for (y, cell_y) in rows {
+            let cols = grid.cells(position.x, ball_radius, grid.cell_size.x, board.width());
+            for (x, cell_x) in cols {
+                if board.cell(cell_y, cell_x) == kind {
+                    continue;
+                }
+                let origin = Position {
+                    x: x as Coordinate * grid.cell_size.x,
+                    y: y as Coordinate * grid.cell_size.y,
+                };
+                let Some(contact) = Contact::find(position, ball_radius, &origin, grid.cell_size)
+                else {
+                    continue;
+                };
+                let approaching =
+                    contact.normal.x * direction.x + contact.normal.y * direction.y < 0.0;
+                let is_deeper = deepest
+                    .as_ref()
+                    .is_none_or(|(deepest, ..)| contact.depth > deepest.depth);
+                if approaching && is_deeper {
+                    deepest = Some((contact, cell_x, cell_y));
+                }
+            }
+        }
+
+        let (contact, cell_x, cell_y) = deepest?;
+        position.x += contact.normal.x * contact.depth;
+        position.y += contact.normal.y * contact.depth;
+        let bounce_kind = if board.cell(cell_y, cell_x).is_wall() {
+            BounceKind::Obstacle
+        } else {
+            // convert the cell to our team
+            board.set_cell(cell_y, cell_x, kind);
+            events.push(Event::CellFlipped {
+                row: cell_y,
+                col: cell_x,
+                by_team: kind,
+            });
+            BounceKind::Cell
+        };
+        Some((contact.collision_type, bounce_kind))
+    }
+
+    /// Find the contact of the ball with any of the paddles.
+    ///
+    /// The ball is pushed out of the paddle even if it's moving away,
+    /// so that a paddle moving sideways doesn't swallow the ball.
+    /// A ball approaching the top or the bottom of the paddle is deflected
+    /// at an angle proportional to the distance from the middle of the paddle,
+    /// otherwise it bounces off as it would off a cell.
+    fn paddles(
+        position: &mut Position,
+        direction: &Position,
+        ball_radius: Coordinate,
+        paddles: &[Paddle],
+        paddle_size: &Position,
+        max_angle: Coordinate,
+    ) -> Option<CollisionType> {
+        let (paddle, contact) = paddles.iter().find_map(|paddle| {
+            let origin = Position {
+                x: paddle.position.x - paddle_size.x / 2.0,
+                y: paddle.position.y - paddle_size.y / 2.0,
+            };
+            Contact::find(position, ball_radius, &origin, paddle_size)
+                .map(|contact| (paddle, contact))
+        })?;
+        position.x += contact.normal.x * contact.depth;
+        position.y += contact.normal.y * contact.depth;
+        if contact.normal.x * direction.x + contact.normal.y * direction.y >= 0.0 {
+            return None;
+        }
+        if contact.collision_type != CollisionType::Vertical {
+            return Some(contact.collision_type);
+        }
+        let offset = ((position.x - paddle.position.x) / (paddle_size.x / 2.0)).clamp(-1.0, 1.0);
+        let (sin, cos) = (offset * max_angle).to_radians().sin_cos();
+        Some(CollisionType::Deflected(Position {
+            x: sin,
+            y: contact.normal.y * cos,
+        }))
+    }
+}
+
+/// Placement of the board cells within the viewport.
+#[derive(Debug, Clone, Copy)]
+struct Grid<'a> {
+    cell_size: &'a Position,
+    /// The board repeats beyond the edges of the viewport (see [`Boundaries::Wrap`]).
+    wrap: bool,
+}
+
+impl Grid<'_> {
+    /// The cells along a single axis overlapped by the ball.
+    ///
+    /// Returns the position of every cell within the grid (which might be
+    /// beyond the viewport) and the index of the corresponding cell of the board.
+    /// Without wrapping the cells beyond the board are skipped,
+    /// otherwise their indices continue from the opposite edge.
+    fn cells(
+        &self,
+        center: Coordinate,
+        ball_radius: Coordinate,
+        cell_size: Coordinate,
+        count: board::Index,
+    ) -> impl Iterator<Item = (i32, board::Index)> {
+        let first = ((center - ball_radius) / cell_size).floor() as i32;
+        let last = ((center + ball_radius) / cell_size).floor() as i32;
+        let (wrap, count) = (self.wrap, count as i32);
+        (first..=last).filter_map(move |cell| {
+            if wrap {
+                Some((cell, cell.rem_euclid(count) as board::Index))
+            } else {
+                (0..count)
+                    .contains(&cell)
+                    .then_some((cell, cell as board::Index))
+            }
+        })
+    }
+}
+
+/// Intersection of the ball with a single cell.
+#[derive(Debug, Clone, PartialEq)]
+struct Contact {
+    /// Unit vector pointing out of the cell towards the center of the ball.
+    normal: Position,
+    /// How deep the ball overlaps the cell along the normal.
+    depth: Coordinate,
+    collision_type: CollisionType,
+}
+
+impl Contact {
+    /// Intersect the ball with the rectangle of given top-left corner and size
+    /// (e.g. a cell of the grid).
+    ///
+    /// Touching the rectangle is not considered a contact.
+    fn find(
+        position: &Position,
+        ball_radius: Coordinate,
+        origin: &Position,
+        size: &Position,
+    ) -> Option<Self> {
+        let (left, top) = (origin.x, origin.y);
+        let (right, bottom) = (left + size.x, top + size.y);
+        // the point of the cell closest to the center of the ball.
+        let distance_x = position.x - position.x.clamp(left, right);
+        let distance_y = position.y - position.y.clamp(top, bottom);
+        let distance = distance_x.hypot(distance_y);
+        if distance >= ball_radius {
+            return None;
+        }
+        if distance > 0.0 {
+            let normal = Position {
+                x: distance_x / distance,
+                y: distance_y / distance,
+            };
+            let collision_type = if distance_x == 0.0 {
+                CollisionType::Vertical
+            } else if distance_y == 0.0 {
+                CollisionType::Horizontal
+            } else {
+                CollisionType::Corner(normal.clone())
+            };
+            return Some(Self {
+                normal,
+                depth: ball_radius - distance,
+                collision_type,
+            });
+        }
+
+        // the center is inside the cell, so leave through the closest edge.
+        let edges = [
+            (position.x - left, -1.0, 0.0, CollisionType::Horizontal),
+            (right - position.x, 1.0, 0.0, CollisionType::Horizontal),
+            (position.y - top, 0.0, -1.0, CollisionType::Vertical),
+            (bottom - position.y, 0.0, 1.0, CollisionType::Vertical),
+        ];
+        let (distance, x, y, collision_type) = edges
+            .into_iter()
+            .min_by(|a, b| a.0.total_cmp(&b.0))
+            .expect("there are four edges");
+        Some(Self {
+            normal: Position { x, y },
+            depth: ball_radius + distance,
+            collision_type,
+        })
+    }
+}
+
+/// The way the ball bounces off an obstacle.
+#[derive(Debug, Clone, PartialEq)]
+enum CollisionType {
+    /// Hit a vertical edge, the horizontal movement is reversed.
+    Horizontal,
+    /// Hit a horizontal edge, the vertical movement is reversed.
+    Vertical,
+    /// Hit a corner, the movement is reflected off the given normal (a unit vector).
+    Corner(Position),
+    /// Hit two perpendicular edges at once, both movements are reversed.
+    Both,
+    /// Hit a paddle, the movement continues in the given direction (a unit vector).
+    Deflected(Position),
+}
+
+/// Outcome of the ball reaching the edge of the viewport.
+#[derive(Debug, Clone, PartialEq)]
+enum Boundary {
+    /// The ball bounces off the edge.
+    Bounce(CollisionType),
+    /// The ball is lost (see [`Boundaries::Absorb`]).
+    Absorbed,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layout::Layout;
+
+    const INITIAL_SPEED: Coordinate = 0.5;
+
+    fn assert_angle(actual: Coordinate, expected: Coordinate) {
+        let diff = (actual - expected).rem_euclid(360.0);
+        assert!(
+            diff.min(360.0 - diff) < 0.001,
+            "Expected angle {expected}, got {actual}"
+        );
+    }
+
+    #[test]
+    fn should_move_with_constant_speed_in_every_direction() {
+        for angle in 0..360 {
+            for speed in [INITIAL_SPEED, 2.0 * INITIAL_SPEED] {
+                let movement = Movement::new(angle as Coordinate + 0.5, speed);
+                let mut position = Position { x: 0.0, y: 0.0 };
+
+                // when
+                movement.apply(100.0, &mut position);
+
+                // then
+                let expected = speed * 100.0;
+                let distance = position.x.hypot(position.y);
+                assert!(
+                    (distance - expected).abs() < 0.001,
+                    "Moved {distance} instead of {expected} at {angle}"
+                );
+                assert_angle(movement.angle(), angle as Coordinate + 0.5);
+            }
+        }
+    }
+
+    #[test]
+    fn should_move_along_the_heading() {
+        let movement = Movement::new(90.0, INITIAL_SPEED);
+        let mut position = Position { x: 10.0, y: 10.0 };
+
+        // when
+        movement.apply(10.0, &mut position);
+
+        // then
+        assert!((position.x - 10.0).abs() < 0.001);
+        assert!((position.y - 15.0).abs() < 0.001);
+    }
+
+    #[test]
+    fn should_calculate_horizontal_bounce_angle_correctly() {
+        let values = vec![
+            (0, 180),
+            (15, 165),
+            (30, 150),
+            (65, 115),
+            (89, 91),
+            (90, 90),   //edge case?
+            (270, 270), //edge case?
+            (105, 75),
+        ];
+
+        for (angle, expected) in values {
+            let mut mov = Movement::new(angle as Coordinate, 0.0);
+            // when
+            mov.bounce(
+                CollisionType::Horizontal,
+                &GameConfig::default(),
+                &mut Rng::new(0),
+            );
+
+            // then
+            assert_angle(mov.angle(), expected as Coordinate);
+        }
+    }
+
+    #[test]
+    fn should_calculate_vertical_bounce_angle_correctly() {
+        let values = vec![
+            (90, 270),
+            (0, 0),     //edge case?
+            (180, 180), //edge case?,
+            (120, 240),
+            (280, 80),
+        ];
+
+        for (angle, expected) in values {
+            let mut mov = Movement::new(angle as Coordinate, 0.0);
+            // when
+            mov.bounce(
+                CollisionType::Vertical,
+                &GameConfig::default(),
+                &mut Rng::new(0),
+            );
+
+            // then
+            assert_angle(mov.angle(), expected as Coordinate);
+        }
+    }
+
+    #[test]
+    fn should_skew_the_bounce_based_on_speed() {
+        let mut mov = Movement::new(30.0, INITIAL_SPEED);
+
+        // when
+        mov.bounce(
+            CollisionType::Vertical,
+            &GameConfig::default(),
+            &mut Rng::new(0),
+        );
+
+        // then
+        assert_angle(mov.angle(), 333.0);
+        assert_eq!(mov.speed, INITIAL_SPEED + 0.005);
+        assert!((mov.direction.x.hypot(mov.direction.y) - 1.0).abs() < 0.001);
+    }
+
+    #[test]
+    fn should_not_find_collisions() {
+        let kind = board::State::LIT;
+        let ball_radius = 10f32;
+
+        let cell_size = Position { x: 10.0, y: 10.0 };
+        let mut position = Position { x: 10.0, y: 15.0 };
+        let mut board = Board::square(5);
+        let mut events = Vec::new();
+
+        // we are only touching Lit board cells,
+        // so there should be no collisions.
+        // However bounding rectangle is touching cells at (2,0) and (2,2)
+        // hence we are testing if these collisions are omitted.
+        let collision = Collisions::board(
+            &mut position,
+            &Position { x: 1.0, y: 0.0 },
+            ball_radius,
+            &Grid {
+                cell_size: &cell_size,
+                wrap: false,
+            },
+            &mut board,
+            kind,
+            &mut events,
+        );
+
+        // no change
+        assert_eq!(collision, None);
+        assert_eq!(events, vec![]);
+    }
+
+    #[test]
+    fn should_flip_a_single_cell_per_contact() {
+        let cell_size = Position { x: 10.0, y: 10.0 };
+        let mut board: Board = "######\n######\n##..##\n######".parse().unwrap();
+        // heading down right at the boundary between the two dark cells.
+        let mut position = Position { x: 30.0, y: 16.0 };
+        let mut events = Vec::new();
+
+        // when
+        let collision = Collisions::board(
+            &mut position,
+            &Position { x: 0.0, y: 1.0 },
+            5.0,
+            &Grid {
+                cell_size: &cell_size,
+                wrap: false,
+            },
+            &mut board,
+            board::State::LIT,
+            &mut events,
+        );
+
+        // then
+        assert_eq!(collision, Some((CollisionType::Vertical, BounceKind::Cell)));
+        assert_eq!(position, Position { x: 30.0, y: 15.0 });
+        assert_eq!(
+            events,
+            vec![Event::CellFlipped {
+                row: 2,
+                col: 2,
+                by_team: board::State::LIT
+            }]
+        );
+        assert_eq!(board.cell(2, 3), board::State::DARK);
+    }
+
+    #[test]
+    fn should_bounce_off_the_corner_along_the_normal() {
+        let viewport_size = Position { x: 100.0, y: 100.0 };
+        let mut board: Board = "##########\n".repeat(10).parse().unwrap();
+        board.set_cell(5, 5, board::State::DARK);
+        let config = GameConfig::builder().angle_skew(0.0).build();
+        let mut game = Game::new(board, 0, viewport_size, config);
+        game.balls = vec![Ball::new(
+            board::State::LIT,
+            Position { x: 40.0, y: 40.0 },
+            Movement::new(45.0, INITIAL_SPEED),
+        )];
+
+        // when
+        game.tick(30);
+
+        // then
+        assert_eq!(game.board().cell(5, 5), board::State::LIT);
+        assert_angle(game.balls[0].movement.angle(), 225.0);
+        let Position { x, y } = game.balls[0].position;
+        assert!((50.0 - x).hypot(50.0 - y) >= game.ball_radius());
+    }
+
+    #[test]
+    fn should_reflect_off_both_edges_in_the_corner() {
+        let viewport_size = Position { x: 100.0, y: 100.0 };
+        let mut position = Position { x: 2.0, y: 99.0 };
+        let mut previous_position = position.clone();
+        let direction = Position { x: -0.6, y: 0.8 };
+
+        // when
+        let boundary = Collisions::boundaries(
+            &mut position,
+            &mut previous_position,
+            &direction,
+            5.0,
+            &viewport_size,
+            Boundaries::Reflect,
+        );
+
+        // then
+        assert_eq!(boundary, Some(Boundary::Bounce(CollisionType::Both)));
+        assert_eq!(position, Position { x: 5.0, y: 95.0 });
+    }
+
+    #[test]
+    fn should_wrap_balls_around_the_viewport() {
+        let viewport_size = Position { x: 100.0, y: 100.0 };
+        let board: Board = "##########\n".repeat(10).parse().unwrap();
+        let config = GameConfig::builder().boundaries(Boundaries::Wrap).build();
+        let mut game = Game::new(board, 0, viewport_size, config);
+        game.balls = vec![Ball::new(
+            board::State::LIT,
+            Position { x: 3.0, y: 50.0 },
+            Movement::new(180.0, INITIAL_SPEED),
+        )];
+
+        // when
+        game.tick(10);
+        game.tick(15);
+
+        // then
+        let Position { x, .. } = game.balls[0].position;
+        assert!((x - 98.0).abs() < 0.001, "Unexpected position {x}");
+        // blended with the previous position shifted by the wrap, not across the viewport.
+        let interpolated = game.interpolated_ball(0).unwrap();
+        assert!((interpolated.x - 100.5).abs() < 0.001);
+        assert_eq!(game.drain_events().count(), 0);
+    }
+
+    #[test]
+    fn should_hit_cells_across_the_wrapped_edge() {
+        let viewport_size = Position { x: 100.0, y: 100.0 };
+        let board: Board = ".#########\n".repeat(10).parse().unwrap();
+        let config = GameConfig::builder().boundaries(Boundaries::Wrap).build();
+        let mut game = Game::new(board, 0, viewport_size, config);
+        game.balls = vec![Ball::new(
+            board::State::LIT,
+            Position { x: 90.0, y: 55.0 },
+            Movement::new(0.0, INITIAL_SPEED),
+        )];
+
+        // when
+        game.tick(20);
+
+        // then
+        assert_eq!(game.board().cell(5, 0), board::State::LIT);
+        assert!(game.balls[0].movement.direction.x < 0.0);
+        assert!(game.balls[0].position.x <= 95.0);
+        assert!(game.drain_events().any(|event| matches!(
+            event,
+            Event::BallBounced {
+                kind: BounceKind::Cell,
+                ..
+            }
+        )));
+    }
+
+    #[test]
+    fn should_draw_the_ball_on_every_wrapped_edge() {
+        let viewport_size = Position { x: 100.0, y: 100.0 };
+        let config = GameConfig::builder().boundaries(Boundaries::Wrap).build();
+        let wrapped = Game::new(Board::square(10), 0, viewport_size.clone(), config);
+        let classic = Game::new(Board::square(10), 0, viewport_size, GameConfig::default());
+        let corner = Position { x: 2.0, y: 99.0 };
+
+        // when
+        let images = wrapped.ball_images(&corner);
+
+        // then
+        assert_eq!(
+            images,
+            vec![
+                Position { x: 2.0, y: 99.0 },
+                Position { x: 102.0, y: 99.0 },
+                Position { x: 2.0, y: -1.0 },
+                Position { x: 102.0, y: -1.0 },
+            ]
+        );
+        assert_eq!(wrapped.ball_images(&Position { x: 50.0, y: 50.0 }).len(), 1);
+        assert_eq!(classic.ball_images(&corner), vec![corner]);
+    }
+
+    #[test]
+    fn should_end_the_round_when_a_ball_is_absorbed() {
+        let viewport_size = Position { x: 100.0, y: 100.0 };
+        let board: Board = "##########\n".repeat(10).parse().unwrap();
+        let config = GameConfig::builder().boundaries(Boundaries::Absorb).build();
+        let mut game = Game::new(board, 0, viewport_size, config);
+        game.balls = vec![Ball::new(
+            board::State::LIT,
+            Position { x: 10.0, y: 50.0 },
+            Movement::new(180.0, INITIAL_SPEED),
+        )];
+
+        // when
+        game.tick(100);
+
+        // then
+        assert_eq!(
+            game.status(),
+            GameStatus::Finished {
+                winner: Some(board::State::DARK)
+            }
+        );
+        assert_eq!(game.balls[0].position.x, 5.0);
+        let events: Vec<_> = game.drain_events().collect();
+        assert!(matches!(
+            events.as_slice(),
+            [
+                Event::BallAbsorbed { ball: 0, .. },
+                Event::GameFinished { .. }
+            ]
+        ));
+    }
+
+    #[test]
+    fn should_deflect_the_ball_depending_on_where_it_hits_the_paddle() {
+        let viewport_size = Position { x: 100.0, y: 100.0 };
+        let board: Board = "##########\n".repeat(10).parse().unwrap();
+        for (x, expected_angle) in [(50.0, 270.0), (60.0, 300.0), (40.0, 240.0)] {
+            let mut game = Game::new(
+                board.clone(),
+                0,
+                viewport_size.clone(),
+                GameConfig::default(),
+            );
+            game.add_paddle(board::State::DARK, Position { x: 50.0, y: 90.0 });
+            game.balls = vec![Ball::new(
+                board::State::LIT,
+                Position { x, y: 70.0 },
+                Movement::new(90.0, INITIAL_SPEED),
+            )];
+
+            // when
+            game.tick(100);
+
+            // then
+            assert_angle(game.balls[0].movement.angle(), expected_angle);
+            assert!(game.balls[0].position.y < 70.0);
+            let events: Vec<_> = game.drain_events().collect();
+            assert!(events.contains(&Event::BallBounced {
+                ball: 0,
+                kind: BounceKind::Paddle,
+                position: Position { x, y: 82.5 },
+            }));
+        }
+    }
+
+    #[test]
+    fn should_steer_the_paddle_towards_the_target() {
+        let viewport_size = Position { x: 100.0, y: 100.0 };
+        let mut game = Game::new(Board::square(10), 0, viewport_size, GameConfig::default());
+        game.add_paddle(board::State::DARK, Position { x: 50.0, y: 95.0 });
+
+        // when
+        game.set_paddle_target(board::State::DARK, 0.0);
+        game.tick(30);
+
+        // then
+        let paddle = game.paddle(board::State::DARK).unwrap();
+        assert_eq!(paddle.position(), &Position { x: 26.0, y: 95.0 });
+        assert_eq!(game.paddle_size(), Position { x: 40.0, y: 5.0 });
+
+        // when
+        game.tick(100);
+
+        // then the paddle stops at the edge of the viewport
+        let paddle = game.paddle(board::State::DARK).unwrap();
+        assert_eq!(paddle.position().x, 20.0);
+        assert_eq!(
+            game.try_set_paddle_target(board::State::LIT, 0.0),
+            Err(Error::NoPaddle(board::State::LIT.id()))
+        );
+        assert_eq!(
+            game.try_add_paddle(board::State::team(2), Position { x: 1.0, y: 1.0 }),
+            Err(Error::UnknownTeam(2))
+        );
+        assert!(game.remove_paddle(board::State::DARK).is_some());
+        assert!(game.paddles().is_empty());
+    }
+
+    #[test]
+    fn should_not_depend_on_frame_rate() {
+        let viewport_size = Position { x: 320.0, y: 320.0 };
+        let mut slow = Game::new(
+            Board::square(16),
+            0,
+            viewport_size.clone(),
+            GameConfig::default(),
+        );
+        let mut fast = Game::new(Board::square(16), 0, viewport_size, GameConfig::default());
+
+        // when
+        for time in (50..=5000).step_by(50) {
+            slow.tick(time);
+        }
+        for time in (16..5000).step_by(16).chain([5000]) {
+            fast.tick(time);
+        }
+
+        // then
+        assert_eq!(slow.balls(), fast.balls());
+        assert_eq!(slow.board().raw_state(), fast.board().raw_state());
+    }
+
+    #[test]
+    fn should_drop_the_time_beyond_the_steps_limit() {
+        let viewport_size = Position { x: 320.0, y: 320.0 };
+        let mut game = Game::new(Board::square(16), 0, viewport_size, GameConfig::default());
+
+        // when
+        game.tick(60 * 60 * 1000);
+
+        // then
+        assert_eq!(game.elapsed(), MAX_STEPS_PER_TICK * game.config().step_ms());
+        assert_eq!(game.alpha(), 0.0);
+
+        // when
+        game.tick(Timestamp::MAX);
+
+        // then
+        assert_eq!(
+            game.elapsed(),
+            2 * MAX_STEPS_PER_TICK * game.config().step_ms()
+        );
+    }
+
+    #[test]
+    fn should_limit_the_sub_steps_of_fast_balls() {
+        let viewport_size = Position { x: 160.0, y: 160.0 };
+        let config = GameConfig::builder()
+            .initial_speed(1e9)
+            .max_speed(1e9)
+            .build();
+        let mut game = Game::new(Board::square(16), 0, viewport_size, config);
+
+        // when
+        game.tick(100);
+
+        // then
+        for ball in game.balls() {
+            let Position { x, y } = ball.position();
+            assert!((5.0..=155.0).contains(x) && (5.0..=155.0).contains(y));
+        }
+    }
+
+    #[test]
+    fn should_not_tunnel_through_cells_on_long_steps() {
+        let viewport_size = Position { x: 160.0, y: 160.0 };
+        let mut board = Board::square(16);
+        for row in 0..16 {
+            board.flip(row, 5);
+        }
+        let config = GameConfig::builder().step_ms(60).build();
+        let mut game = Game::new(board, 0, viewport_size, config);
+        game.balls[0] = Ball::new(
+            board::State::LIT,
+            Position { x: 25.0, y: 85.0 },
+            Movement::new(0.0, 2.0 * INITIAL_SPEED),
+        );
+
+        // when
+        game.tick(60);
+
+        // then
+        assert_eq!(game.board().cell(8, 5), board::State::LIT);
+        assert!(game.balls[0].position.x < 50.0);
+        assert!(game.balls[0].movement.direction.x < 0.0);
+        for row in 0..16 {
+            assert_eq!(game.board().cell(row, 8), board::State::DARK);
+        }
+    }
+
+    #[test]
+    fn should_bounce_off_walls_without_flipping_them() {
+        let viewport_size = Position { x: 160.0, y: 160.0 };
+        let board: Board = "#####X##........\n".repeat(16).parse().unwrap();
+        let config = GameConfig::builder().step_ms(60).build();
+        let mut game = Game::new(board, 0, viewport_size, config);
+        game.balls = vec![Ball::new(
+            board::State::LIT,
+            Position { x: 25.0, y: 85.0 },
+            Movement::new(0.0, 2.0 * INITIAL_SPEED),
+        )];
+
+        // when
+        game.tick(60);
+
+        // then
+        assert_eq!(game.board().cell(8, 5), board::State::WALL);
+        assert_eq!(game.board().count(board::State::WALL), 16);
+        assert!(game.balls[0].position.x < 50.0);
+        assert!(game.balls[0].movement.direction.x < 0.0);
+        let events: Vec<_> = game.drain_events().collect();
+        assert!(events.iter().any(|event| matches!(
+            event,
+            Event::BallBounced {
+                kind: BounceKind::Obstacle,
+                ..
+            }
+        )));
+        assert!(events
+            .iter()
+            .all(|event| !matches!(event, Event::CellFlipped { col: 5.., .. })));
+    }
+
+    #[test]
+    fn should_bounce_balls_off_each_other() {
+        let viewport_size = Position { x: 160.0, y: 160.0 };
+        let new_game = |collisions| {
+            let board: Board = "################\n".repeat(16).parse().unwrap();
+            let config = GameConfig::builder().ball_collisions(collisions).build();
+            let mut game = Game::new(board, 0, viewport_size.clone(), config);
+            game.balls = vec![
+                Ball::new(
+                    board::State::LIT,
+                    Position { x: 40.0, y: 80.0 },
+                    Movement::new(0.0, INITIAL_SPEED),
+                ),
+                Ball::new(
+                    board::State::LIT,
+                    Position { x: 120.0, y: 80.0 },
+                    Movement::new(180.0, INITIAL_SPEED),
+                ),
+            ];
+            game
+        };
+        let mut ghosts = new_game(BallCollisions::Off);
+        let mut bouncing = new_game(BallCollisions::SameTeamOnly);
+
+        // when
+        ghosts.tick(100);
+        bouncing.tick(100);
+
+        // then
+        assert!(ghosts.balls[0].position.x > ghosts.balls[1].position.x);
+        assert!(bouncing.balls[0].position.x < 70.0);
+        assert!(bouncing.balls[0].movement.direction.x < 0.0);
+        assert!(bouncing.balls[1].position.x > 90.0);
+        assert!(bouncing.balls[1].movement.direction.x > 0.0);
+        let collisions: Vec<_> = bouncing
+            .drain_events()
+            .filter(|event| matches!(event, Event::BallsCollided { .. }))
+            .collect();
+        let [Event::BallsCollided {
+            ball: 0,
+            other: 1,
+            position,
+        }] = collisions.as_slice()
+        else {
+            panic!("Expected a single collision, got {collisions:?}");
+        };
+        assert!((position.x - 80.0).abs() < 1.0);
+        assert!(!BallCollisions::SameTeamOnly.applies(board::State::LIT, board::State::DARK));
+    }
+
+    #[test]
+    fn should_keep_colliding_balls_within_the_viewport() {
+        let viewport_size = Position { x: 160.0, y: 160.0 };
+        let board: Board = "################\n".repeat(16).parse().unwrap();
+        let config = GameConfig::builder()
+            .initial_speed(1.0)
+            .max_speed(2.0)
+            .ball_collisions(BallCollisions::Bounce)
+            .build();
+        let mut game = Game::new(board, 0, viewport_size, config);
+        // the balls meet right next to the edge, the right one bounces towards it.
+        game.balls = vec![
+            Ball::new(
+                board::State::LIT,
+                Position { x: 140.0, y: 80.0 },
+                Movement::new(0.0, 1.0),
+            ),
+            Ball::new(
+                board::State::LIT,
+                Position { x: 152.0, y: 80.0 },
+                Movement::new(180.0, 1.0),
+            ),
+        ];
+
+        // when
+        game.tick(10);
+
+        // then
+        assert!(game.balls[1].position.x <= 155.0);
+        assert!(game.balls[1].movement.direction.x < 0.0);
+        let events: Vec<_> = game.drain_events().collect();
+        assert!(matches!(
+            events.as_slice(),
+            [
+                Event::BallsCollided { .. },
+                ..,
+                Event::BallBounced {
+                    ball: 1,
+                    kind: BounceKind::Wall,
+                    ..
+                },
+                Event::SpeedChanged { ball: 1, .. }
+            ]
+        ));
+    }
+
+    #[test]
+    fn should_interpolate_between_steps() {
+        let viewport_size = Position { x: 320.0, y: 320.0 };
+        let config = GameConfig::builder().step_ms(20).build();
+        let mut game = Game::new(Board::square(16), 0, viewport_size, config);
+
+        // when
+        game.tick(25);
+
+        // then
+        assert_eq!(game.alpha(), 0.25);
+        let previous = &game.balls[0].previous_position;
+        let current = game.balls[0].position();
+        let interpolated = game.interpolated_ball(0).unwrap();
+        assert_eq!(interpolated.x, previous.x + (current.x - previous.x) * 0.25);
+        assert_eq!(interpolated.y, previous.y + (current.y - previous.y) * 0.25);
+
+        // when
+        game.tick(40);
+
+        // then
+        assert_eq!(game.alpha(), 0.0);
+        assert_eq!(
+            game.interpolated_ball(0),
+            Some(game.balls[0].previous_position.clone())
+        );
+    }
+
+    #[test]
+    fn should_add_and_remove_balls() {
+        let viewport_size = Position { x: 160.0, y: 160.0 };
+        let mut game = Game::new(Board::square(16), 0, viewport_size, GameConfig::default());
+
+        // when
+        let index = game.add_ball(board::State::LIT, Position { x: 20.0, y: 20.0 }, 90.0);
+        game.add_ball(board::State::DARK, Position { x: 140.0, y: 20.0 }, 90.0);
+
+        // then
+        assert_eq!(index, 2);
+        let teams: Vec<_> = game.balls().iter().map(Ball::team).collect();
+        use board::State;
+        assert_eq!(
+            teams,
+            vec![State::LIT, State::DARK, State::LIT, State::DARK]
+        );
+
+        // when
+        let removed = game.remove_ball(0);
+
+        // then
+        assert_eq!(removed.map(|b| b.team()), Some(State::LIT));
+        assert_eq!(game.remove_ball(3), None);
+        assert_eq!(game.balls().len(), 3);
+        assert_eq!(
+            game.ball(1).unwrap().position(),
+            &Position { x: 20.0, y: 20.0 }
+        );
+
+        // all the balls are moved
+        game.tick(100);
+        assert_eq!(
+            game.ball(1).unwrap().position(),
+            &Position { x: 20.0, y: 70.0 }
+        );
+    }
+
+    #[test]
+    fn should_start_with_a_ball_per_team() {
+        let viewport_size = Position { x: 400.0, y: 100.0 };
+        let game = Game::new(
+            Board::with_teams(16, 4, 4),
+            0,
+            viewport_size,
+            GameConfig::default(),
+        );
+
+        let balls: Vec<_> = game
+            .balls()
+            .iter()
+            .map(|ball| (ball.team().id(), ball.position().clone()))
+            .collect();
+
+        assert_eq!(
+            balls,
+            vec![
+                (1, Position { x: 50.0, y: 50.0 }),
+                (0, Position { x: 150.0, y: 50.0 }),
+                (2, Position { x: 250.0, y: 50.0 }),
+                (3, Position { x: 350.0, y: 50.0 }),
+            ]
+        );
+        for ball in game.balls() {
+            let cell_x = (ball.position().x / game.cell_size().x) as board::Index;
+            let cell_y = (ball.position().y / game.cell_size().y) as board::Index;
+            assert_eq!(game.board().cell(cell_y, cell_x), ball.team());
+        }
+    }
+
+    #[test]
+    fn should_convert_cells_of_other_teams() {
+        let viewport_size = Position { x: 90.0, y: 90.0 };
+        let mut game = Game::new(
+            Board::with_teams(9, 9, 3),
+            0,
+            viewport_size,
+            GameConfig::default(),
+        );
+        // the team 2 ball heading left towards the dark stripe
+        game.balls = vec![Ball::new(
+            board::State::team(2),
+            Position { x: 75.0, y: 45.0 },
+            Movement::new(180.0, INITIAL_SPEED),
+        )];
+
+        // when
+        game.tick(40);
+
+        // then
+        assert_eq!(game.board().cell(4, 5), board::State::team(2));
+        assert!(game.balls[0].movement.direction.x > 0.0);
+    }
+
+    #[test]
+    fn should_reproduce_seeded_games() {
+        let viewport_size = Position { x: 320.0, y: 320.0 };
+        let new_game = |seed| {
+            let config = GameConfig::builder().seed(seed).bounce_jitter(5.0).build();
+            Game::new(Board::square(16), 0, viewport_size.clone(), config)
+        };
+        let mut a = new_game(7);
+        let mut b = new_game(7);
+        let mut c = new_game(8);
+
+        // the balls start within their own stripes
+        for ball in a.balls() {
+            let cell_x = (ball.position().x / a.cell_size().x) as board::Index;
+            let cell_y = (ball.position().y / a.cell_size().y) as board::Index;
+            assert_eq!(a.board().cell(cell_y, cell_x), ball.team());
+        }
+
+        // when
+        for time in (16..10_000).step_by(16) {
+            a.tick(time);
+            b.tick(time);
+            c.tick(time);
+        }
+
+        // then
+        assert_eq!(a.balls(), b.balls());
+        assert_eq!(a.board().raw_state(), b.board().raw_state());
+        assert_ne!(a.balls(), c.balls());
+    }
+
+    #[test]
+    fn should_emit_events_for_every_change() {
+        let viewport_size = Position { x: 320.0, y: 320.0 };
+        let mut game = Game::new(Board::square(16), 0, viewport_size, GameConfig::default());
+        let mut board = Board::square(16);
+
+        // when
+        game.tick(5000);
+        let events: Vec<_> = game.drain_events().collect();
+
+        // then
+        for event in &events {
+            if let Event::CellFlipped { row, col, by_team } = event {
+                board.set_cell(*row, *col, *by_team);
+            }
+        }
+        assert_eq!(board.raw_planes(), game.board().raw_planes());
+        let bounced = |expected| {
+            events
+                .iter()
+                .any(|event| matches!(event, Event::BallBounced { kind, .. } if *kind == expected))
+        };
+        assert!(bounced(BounceKind::Wall));
+        assert!(bounced(BounceKind::Cell));
+        assert!(events
+            .iter()
+            .any(|event| matches!(event, Event::SpeedChanged { .. })));
+        assert_eq!(game.events(), &[]);
+    }
+
+    #[test]
+    fn should_finish_the_game_after_time_limit() {
+        let viewport_size = Position { x: 320.0, y: 320.0 };
+        let config = GameConfig::builder().time_limit(1000).build();
+        let mut game = Game::new(Board::square(16), 0, viewport_size, config);
+
+        // when
+        game.tick(990);
+
+        // then
+        assert_eq!(game.status(), GameStatus::Running);
+
+        // when
+        game.tick(1500);
+
+        // then
+        let scores = game.scoreboard();
+        let winner = (scores[0].cells() > scores[1].cells()).then(|| scores[0].team());
+        assert_eq!(game.status(), GameStatus::Finished { winner });
+        assert_eq!(game.elapsed(), 1000);
+        assert_eq!(scores.iter().map(Score::cells).sum::<u32>(), 256);
+        let finished = game.drain_events().last();
+        assert_eq!(finished, Some(Event::GameFinished { winner }));
+
+        // the physics is not advanced anymore
+        let balls = game.balls().to_vec();
+        game.tick(2000);
+        assert_eq!(game.balls(), &balls[..]);
+        assert_eq!(game.elapsed(), 1000);
+    }
+
+    #[test]
+    fn should_finish_the_game_on_territory_or_flips() {
+        let viewport_size = Position { x: 320.0, y: 320.0 };
+        let territory = GameConfig::builder().territory_threshold(0.55).build();
+        let flips = GameConfig::builder().flips_to_win(10).build();
+        let mut territory = Game::new(Board::square(16), 0, viewport_size.clone(), territory);
+        let mut flips = Game::new(Board::square(16), 0, viewport_size, flips);
+
+        // when
+        for time in (100..=60_000).step_by(100) {
+            territory.tick(time);
+            flips.tick(time);
+        }
+
+        // then
+        let GameStatus::Finished {
+            winner: Some(winner),
+        } = territory.status()
+        else {
+            panic!("Expected a winner, got {:?}", territory.status());
+        };
+        assert!(territory.board().territory(winner) >= 0.55);
+        let GameStatus::Finished {
+            winner: Some(winner),
+        } = flips.status()
+        else {
+            panic!("Expected a winner, got {:?}", flips.status());
+        };
+        let score = flips.scoreboard().into_iter().find(|s| s.team() == winner);
+        // a single step might flip more than one cell.
+        assert!(score.is_some_and(|s| s.flips() >= 10));
+    }
+
+    #[test]
+    fn should_resume_the_game_from_snapshot() {
+        let viewport_size = Position { x: 320.0, y: 240.0 };
+        let config = GameConfig::builder()
+            .seed(3)
+            .bounce_jitter(2.0)
+            .time_limit(60_000)
+            .build();
+        let mut game = Game::new(Board::with_teams(16, 12, 3), 0, viewport_size, config);
+        game.add_paddle(board::State::team(2), Position { x: 160.0, y: 230.0 });
+        game.set_paddle_target(board::State::team(2), 40.0);
+        game.tick(1234);
+
+        // when
+        let snapshot = game.snapshot();
+        let mut restored = Game::restore(&snapshot).unwrap();
+
+        // then
+        assert_eq!(restored.board(), game.board());
+        assert_eq!(restored.balls(), game.balls());
+        assert_eq!(restored.paddles(), game.paddles());
+        assert_eq!(restored.config(), game.config());
+        assert_eq!(restored.scoreboard(), game.scoreboard());
+        assert_eq!(restored.alpha(), game.alpha());
+        assert_eq!(restored.snapshot(), snapshot);
+
+        // the games go on identically
+        for time in (1250..5000).step_by(16) {
+            game.tick(time);
+            restored.tick(time);
+        }
+        assert_eq!(restored.board(), game.board());
+        assert_eq!(restored.balls(), game.balls());
+    }
+
+    #[test]
+    fn should_reject_corrupted_snapshot() {
+        let viewport_size = Position { x: 320.0, y: 320.0 };
+        let game = Game::new(Board::square(16), 0, viewport_size, GameConfig::default());
+        let mut snapshot = game.snapshot();
+
+        // when
+        snapshot[20] ^= 1;
+        let result = Game::restore(&snapshot);
+
+        // then
+        assert_eq!(
+            result.err(),
+            Some(Error::CorruptedSnapshot("Checksum mismatch"))
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn should_serialize_the_game_state() {
+        let viewport_size = Position { x: 320.0, y: 320.0 };
+        let mut game = Game::new(Board::square(16), 0, viewport_size, GameConfig::default());
+        game.tick(500);
+        let state = game.state();
+
+        // when
+        let json = serde_json::to_string(&state).unwrap();
+        let deserialized: GameState = serde_json::from_str(&json).unwrap();
+
+        // then
+        assert_eq!(deserialized, state);
+        assert!(json.len() < 1024, "The state is not compact: {json}");
+    }
+
+    #[test]
+    fn should_rewind_to_recorded_states() {
+        let viewport_size = Position { x: 320.0, y: 320.0 };
+        let config = GameConfig::builder().rewind_capacity(100).build();
+        let mut game = Game::new(Board::square(16), 0, viewport_size, config);
+        let mut states = vec![];
+        for time in (16..2000).step_by(16) {
+            game.tick(time);
+            states.push((game.elapsed(), game.board().clone(), game.balls().to_vec()));
+        }
+        let (elapsed, board, balls) = &states[states.len() - 50];
+        let latest = game.board().clone();
+        game.take_dirty();
+
+        // when
+        game.rewind_to(elapsed + 5).unwrap();
+
+        // then
+        assert_eq!(game.elapsed(), *elapsed);
+        assert_eq!(game.board(), board);
+        assert_eq!(game.balls(), &balls[..]);
+
+        // when
+        let previous = game.step_back();
+
+        // then
+        let (elapsed, board, balls) = &states[states.len() - 51];
+        assert_eq!(previous, Some(*elapsed));
+        assert_eq!(game.board(), board);
+        assert_eq!(game.balls(), &balls[..]);
+        assert_eq!(game.rewind_to(0), Err(Error::NotRecorded(0)));
+        let dirty = game.take_dirty();
+        for row in 0..16 {
+            for col in 0..16 {
+                let changed = latest.cell(row, col) != game.board().cell(row, col);
+                assert!(!changed || dirty.contains(&(row, col)));
+            }
+        }
+        assert!(
+            dirty.len() < 256,
+            "Only the changed cells should be redrawn"
+        );
+
+        // the game goes on from the rewound state
+        game.tick(2000);
+        assert!(game.elapsed() > *elapsed);
+    }
+
+    #[test]
+    fn should_start_balls_within_their_teams_cells() {
+        let viewport_size = Position { x: 320.0, y: 320.0 };
+        let board = Board::with_layout(16, 16, 4, &Layout::Quadrants);
+
+        // when
+        let game = Game::new(board, 0, viewport_size, GameConfig::default());
+
+        // then
+        for ball in game.balls() {
+            let cell_x = (ball.position().x / game.cell_size().x) as board::Index;
+            let cell_y = (ball.position().y / game.cell_size().y) as board::Index;
+            assert_eq!(game.board().cell(cell_y, cell_x), ball.team());
+        }
+    }
+
+    #[test]
+    fn should_return_errors_instead_of_panicking() {
+        let viewport_size = Position { x: 30.0, y: 30.0 };
+        let config = GameConfig::default();
+
+        let result = Game::try_new(Board::square(32), 0, viewport_size.clone(), config.clone());
+        assert!(matches!(result, Err(Error::ViewportTooSmall)));
+
+        let mut game = Game::try_new(Board::square(4), 10, viewport_size, config).unwrap();
+        assert_eq!(
+            game.try_tick(10),
+            Err(Error::TimeNotAdvanced {
+                previous: 10,
+                current: 10
+            })
+        );
+        assert_eq!(
+            game.try_add_ball(board::State::team(2), Position { x: 1.0, y: 1.0 }, 0.0),
+            Err(Error::UnknownTeam(2))
+        );
+        assert_eq!(game.try_tick(20), Ok(()));
+    }
+
+    #[test]
+    fn should_compute_square_cells_for_wide_viewport() {
+        let viewport_size = Position { x: 960.0, y: 240.0 };
+
+        let (width, height) = Game::board_size_for(&viewport_size, 8);
+        let game = Game::new(
+            Board::new(width, height),
+            0,
+            viewport_size,
+            GameConfig::default(),
+        );
+
+        assert_eq!((width, height), (32, 8));
+        assert_eq!(game.cell_size(), &Position { x: 30.0, y: 30.0 });
     }
 }
@@ -0,0 +1,102 @@
+//! Bounded history of past states.
+//!
+//! Used to rewind the game (see [`crate::game::Game::rewind_to`]).
+
+use std::collections::VecDeque;
+
+use crate::game::Timestamp;
+
+/// A ring buffer of states keyed by increasing timestamps.
+///
+/// Once the capacity is reached, the oldest states are dropped.
+#[derive(Debug, Clone)]
+pub(crate) struct History<T> {
+    capacity: usize,
+    frames: VecDeque<(Timestamp, T)>,
+}
+
+impl<T> History<T> {
+    /// Create an empty history holding at most `capacity` states.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            frames: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    /// Return `true` if the history is keeping any states at all.
+    pub fn is_enabled(&self) -> bool {
+        self.capacity > 0
+    }
+
+    /// Timestamps of the oldest and the latest state.
+    pub fn range(&self) -> Option<(Timestamp, Timestamp)> {
+        let (first, _) = self.frames.front()?;
+        let (last, _) = self.frames.back()?;
+        Some((*first, *last))
+    }
+
+    /// Append the latest state.
+    ///
+    /// The `time` has to be greater than the time of the previous state,
+    /// otherwise the previous state is replaced.
+    pub fn push(&mut self, time: Timestamp, state: T) {
+        if !self.is_enabled() {
+            return;
+        }
+        while self.frames.back().is_some_and(|(last, _)| *last >= time) {
+            self.frames.pop_back();
+        }
+        if self.frames.len() == self.capacity {
+            self.frames.pop_front();
+        }
+        self.frames.push_back((time, state));
+    }
+
+    /// Drop all the states after given time and return the latest remaining one.
+    ///
+    /// Returns `None` (and keeps the history intact) if there
+    /// is no state recorded at or before given time.
+    pub fn rewind_to(&mut self, time: Timestamp) -> Option<&(Timestamp, T)> {
+        let kept = self.frames.partition_point(|(at, _)| *at <= time);
+        if kept == 0 {
+            return None;
+        }
+        self.frames.truncate(kept);
+        self.frames.back()
+    }
+
+    /// Drop the latest state and return the one before it.
+    ///
+    /// Returns `None` (and keeps the history intact) if there is no earlier state.
+    pub fn step_back(&mut self) -> Option<&(Timestamp, T)> {
+        if self.frames.len() < 2 {
+            return None;
+        }
+        self.frames.pop_back();
+        self.frames.back()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_keep_only_latest_states() {
+        let mut history = History::new(3);
+
+        // when
+        for time in 1..=5 {
+            history.push(time * 10, time);
+        }
+
+        // then
+        assert_eq!(history.range(), Some((30, 50)));
+        assert_eq!(history.rewind_to(25), None);
+        assert_eq!(history.rewind_to(45), Some(&(40, 4)));
+        assert_eq!(history.step_back(), Some(&(30, 3)));
+        assert_eq!(history.step_back(), None);
+        assert_eq!(history.range(), Some((30, 30)));
+    }
+}
@@ -0,0 +1,185 @@
+//! Initial board layouts.
+//!
+//! A layout decides which team owns every cell of a new board
+//! (see [`crate::board::Board::with_layout`]).
+//! The teams are always assigned in the same order: "Lit" first,
+//! then "Dark" and then the remaining teams.
+//! Boards can also be authored as text, see [`crate::board::Board::from_ascii`].
+
+use crate::board::{Index, State};
+use crate::rng::Rng;
+
+/// The way the board is initially split between the teams.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum Layout {
+    /// Equal stripes across the longer side of the board.
+    ///
+    /// In case the stripes do not divide the length evenly, every odd row
+    /// (or column) has the boundaries moved one cell further.
+    /// This is the classic layout of [`crate::board::Board::new`].
+    #[default]
+    Stripes,
+    /// Equal parts separated by straight lines of given direction.
+    Halves(Split),
+    /// Alternating squares of given size (in cells, at least `1`).
+    Checkerboard {
+        /// Size of a single square.
+        size: Index,
+    },
+    /// Four quadrants assigned clockwise from the top-left one.
+    ///
+    /// With two teams the opposite quadrants belong to the same team.
+    Quadrants,
+    /// Concentric rings of given width (in cells, at least `1`), starting at the edge.
+    Rings {
+        /// Width of a single ring.
+        width: Index,
+    },
+    /// Every cell assigned to a random team.
+    Random {
+        /// Seed of the generator, the same seed always gives the same board.
+        seed: u64,
+    },
+}
+
+/// Direction of the lines separating the parts of [`Layout::Halves`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Split {
+    /// Left to right parts.
+    Vertical,
+    /// Top to bottom parts.
+    Horizontal,
+    /// Parts from the top-left to the bottom-right corner.
+    Diagonal,
+}
+
+impl Layout {
+    /// Assign a team to every cell of the board, row after row.
+    pub(crate) fn fill(
+        &self,
+        width: Index,
+        height: Index,
+        teams: u8,
+        mut set: impl FnMut(Index, Index, State),
+    ) {
+        let teams = teams as usize;
+        let mut rng = match self {
+            Layout::Random { seed } => Rng::new(*seed),
+            _ => Rng::new(0),
+        };
+        for row in 0..height {
+            for col in 0..width {
+                let part = match self {
+                    Layout::Stripes => Self::stripe(width, height, teams, row, col),
+                    Layout::Halves(split) => {
+                        let along =
+                            |index: Index, length: Index| (index as f32 + 0.5) / length as f32;
+                        let fraction = match split {
+                            Split::Vertical => along(col, width),
+                            Split::Horizontal => along(row, height),
+                            Split::Diagonal => (along(col, width) + along(row, height)) / 2.0,
+                        };
+                        ((fraction * teams as f32) as usize).min(teams - 1)
+                    }
+                    Layout::Checkerboard { size } => {
+                        let size = (*size).max(1);
+                        (row / size + col / size) as usize % teams
+                    }
+                    Layout::Quadrants => {
+                        let right = col >= width / 2;
+                        let bottom = row >= height / 2;
+                        let quadrant = match (bottom, right) {
+                            (false, false) => 0,
+                            (false, true) => 1,
+                            (true, true) => 2,
+                            (true, false) => 3,
+                        };
+                        quadrant % teams
+                    }
+                    Layout::Rings { width: ring } => {
+                        let from_edge = row.min(col).min(height - 1 - row).min(width - 1 - col);
+                        (from_edge / (*ring).max(1)) as usize % teams
+                    }
+                    Layout::Random { .. } => rng.below(teams as u64) as usize,
+                };
+                set(row, col, Self::team(part));
+            }
+        }
+    }
+
+    /// The stripe of the cell in the classic layout.
+    fn stripe(width: Index, height: Index, stripes: usize, row: Index, col: Index) -> usize {
+        let (length, line_index, cell_index) = if width >= height {
+            (width, row, col)
+        } else {
+            (height, col, row)
+        };
+        let offset = if line_index % 2 > 0 { stripes - 1 } else { 0 };
+        let mut stripe = 0;
+        while stripe + 1 < stripes
+            && cell_index as usize >= ((stripe + 1) * length as usize + offset) / stripes
+        {
+            stripe += 1;
+        }
+        stripe
+    }
+
+    /// Team owning given part of the board.
+    fn team(part: usize) -> State {
+        match part {
+            0 => State::LIT,
+            1 => State::DARK,
+            team => State::team(team as u8),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::Board;
+
+    fn view(layout: Layout, teams: u8) -> String {
+        let board = Board::with_layout(6, 4, teams, &layout);
+        let view = format!("\n{:?}", board);
+        view.lines()
+            .filter(|line| !line.starts_with("Board"))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    #[test]
+    fn should_lay_out_the_board() {
+        assert_eq!(
+            view(Layout::Halves(Split::Horizontal), 2),
+            "\n▣▣▣▣▣▣\n▣▣▣▣▣▣\n▢▢▢▢▢▢\n▢▢▢▢▢▢"
+        );
+        assert_eq!(
+            view(Layout::Halves(Split::Diagonal), 2),
+            "\n▣▣▣▣▣▢\n▣▣▣▣▢▢\n▣▣▢▢▢▢\n▣▢▢▢▢▢"
+        );
+        assert_eq!(
+            view(Layout::Checkerboard { size: 2 }, 2),
+            "\n▣▣▢▢▣▣\n▣▣▢▢▣▣\n▢▢▣▣▢▢\n▢▢▣▣▢▢"
+        );
+        assert_eq!(
+            view(Layout::Quadrants, 4),
+            "\n▣▣▣▢▢▢\n▣▣▣▢▢▢\n▥▥▥▤▤▤\n▥▥▥▤▤▤"
+        );
+        assert_eq!(
+            view(Layout::Rings { width: 1 }, 3),
+            "\n▣▣▣▣▣▣\n▣▢▢▢▢▣\n▣▢▢▢▢▣\n▣▣▣▣▣▣"
+        );
+    }
+
+    #[test]
+    fn should_reproduce_random_layout() {
+        let a = Board::with_layout(32, 32, 3, &Layout::Random { seed: 5 });
+        let b = Board::with_layout(32, 32, 3, &Layout::Random { seed: 5 });
+        let c = Board::with_layout(32, 32, 3, &Layout::Random { seed: 6 });
+
+        assert_eq!(a, b);
+        assert_ne!(a, c);
+        assert!(a.counts().iter().all(|count| *count > 250));
+    }
+}
@@ -2,4 +2,15 @@
 //! Game engine in Rust.
 
 pub mod board;
+pub mod config;
+mod error;
+pub mod event;
 pub mod game;
+mod history;
+pub mod layout;
+pub mod replay;
+mod rng;
+pub mod score;
+pub mod snapshot;
+
+pub use error::Error;
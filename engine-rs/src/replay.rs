@@ -0,0 +1,438 @@
+//! Replay recording and deterministic playback.
+//!
+//! Since the game is fully deterministic, it can be reproduced
+//! from its initial state and the sequence of ticks and inputs.
+//! The [`ReplayRecorder`] captures them along with a hash of the state
+//! after every tick, and the [`ReplayPlayer`] re-runs the game
+//! and reports the first tick where the state does not match.
+
+use crate::board::State;
+use crate::error::Error;
+use crate::game::{Coordinate, Game, Position, Timestamp};
+use crate::snapshot::{self, Encode, Reader, Writer};
+
+/// Bytes every replay file starts with.
+pub const MAGIC: [u8; 4] = *b"WGRP";
+
+/// An input changing the game outside of the physics.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Input {
+    /// Add a ball (see [`Game::add_ball`]).
+    AddBall {
+        /// The team of the ball.
+        team: State,
+        /// Starting position of the ball.
+        position: Position,
+        /// Initial heading of the ball (degrees, clockwise from right).
+        angle: Coordinate,
+    },
+    /// Remove the ball at given index (see [`Game::remove_ball`]).
+    RemoveBall {
+        /// Index of the ball.
+        index: usize,
+    },
+    /// Give the team a paddle (see [`Game::add_paddle`]).
+    AddPaddle {
+        /// The team of the paddle.
+        team: State,
+        /// Center of the paddle.
+        position: Position,
+    },
+    /// Remove the paddle of given team (see [`Game::remove_paddle`]).
+    RemovePaddle {
+        /// The team of the paddle.
+        team: State,
+    },
+    /// Steer the paddle of given team (see [`Game::set_paddle_target`]).
+    SetPaddleTarget {
+        /// The team of the paddle.
+        team: State,
+        /// The `x` coordinate the paddle is heading to.
+        x: Coordinate,
+    },
+}
+
+impl Input {
+    /// Apply the input to the game.
+    pub fn apply(&self, game: &mut Game) -> Result<(), Error> {
+        match self {
+            Input::AddBall {
+                team,
+                position,
+                angle,
+            } => game
+                .try_add_ball(*team, position.clone(), *angle)
+                .map(|_| ()),
+            Input::RemoveBall { index } => {
+                game.remove_ball(*index);
+                Ok(())
+            }
+            Input::AddPaddle { team, position } => game.try_add_paddle(*team, position.clone()),
+            Input::RemovePaddle { team } => {
+                game.remove_paddle(*team);
+                Ok(())
+            }
+            Input::SetPaddleTarget { team, x } => game.try_set_paddle_target(*team, *x),
+        }
+    }
+}
+
+/// A single entry of the replay.
+#[derive(Debug, Clone, PartialEq)]
+enum Record {
+    Input(Input),
+    Tick { time: Timestamp, hash: u64 },
+}
+
+/// Recorded game.
+///
+/// Use [`Replay::to_bytes`] to store the replay in a file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Replay {
+    /// Snapshot of the game when the recording started.
+    initial: Vec<u8>,
+    records: Vec<Record>,
+}
+
+impl Replay {
+    /// Number of recorded ticks.
+    pub fn ticks(&self) -> usize {
+        self.records
+            .iter()
+            .filter(|record| matches!(record, Record::Tick { .. }))
+            .count()
+    }
+
+    /// Serialize the replay.
+    ///
+    /// The format follows the layout of the snapshots (see [`crate::snapshot`]).
+    pub fn to_bytes(&self) -> Vec<u8> {
+        snapshot::seal(MAGIC, |writer| self.encode(writer))
+    }
+
+    /// Deserialize the replay created with [`Replay::to_bytes`].
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        snapshot::open(MAGIC, bytes, Self::decode)
+    }
+}
+
+/// Records all the ticks and inputs of a game.
+///
+/// Every tick and input has to go through the recorder,
+/// otherwise the playback is going to diverge.
+/// Note the rewinding (see [`Game::rewind_to`]) is not supported.
+#[derive(Debug)]
+pub struct ReplayRecorder {
+    replay: Replay,
+}
+
+impl ReplayRecorder {
+    /// Start recording from the current state of the game.
+    pub fn new(game: &Game) -> Self {
+        Self {
+            replay: Replay {
+                initial: game.snapshot(),
+                records: Vec::new(),
+            },
+        }
+    }
+
+    /// Advance the game (see [`Game::try_tick`]) and record the tick.
+    ///
+    /// Rejected ticks are not recorded.
+    pub fn tick(&mut self, game: &mut Game, time_ms: Timestamp) -> Result<(), Error> {
+        game.try_tick(time_ms)?;
+        self.replay.records.push(Record::Tick {
+            time: time_ms,
+            hash: game.state_hash(),
+        });
+        Ok(())
+    }
+
+    /// Apply the input to the game and record it.
+    ///
+    /// Rejected inputs are not recorded.
+    pub fn input(&mut self, game: &mut Game, input: Input) -> Result<(), Error> {
+        input.apply(game)?;
+        self.replay.records.push(Record::Input(input));
+        Ok(())
+    }
+
+    /// View the replay recorded so far.
+    pub fn replay(&self) -> &Replay {
+        &self.replay
+    }
+
+    /// Stop recording.
+    pub fn finish(self) -> Replay {
+        self.replay
+    }
+}
+
+/// Outcome of the replay playback.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Verdict {
+    /// The game was reproduced exactly.
+    Identical {
+        /// Number of verified ticks.
+        ticks: usize,
+    },
+    /// The game state after some tick does not match the recording.
+    Diverged {
+        /// Index of the first divergent tick (counting from `0`).
+        tick: usize,
+        /// Time of the divergent tick.
+        time: Timestamp,
+        /// The recorded state hash.
+        expected: u64,
+        /// The state hash of the reproduced game.
+        actual: u64,
+    },
+}
+
+/// Re-runs a recorded game and verifies its state after every tick.
+#[derive(Debug)]
+pub struct ReplayPlayer<'a> {
+    game: Game,
+    records: &'a [Record],
+    ticks: usize,
+}
+
+impl<'a> ReplayPlayer<'a> {
+    /// Restore the initial state of the recorded game.
+    pub fn new(replay: &'a Replay) -> Result<Self, Error> {
+        Ok(Self {
+            game: Game::restore(&replay.initial)?,
+            records: &replay.records,
+            ticks: 0,
+        })
+    }
+
+    /// View the reproduced game.
+    ///
+    /// After a divergence the game is left in the divergent state.
+    pub fn game(&self) -> &Game {
+        &self.game
+    }
+
+    /// Play the replay until the end or until the first divergent tick.
+    ///
+    /// Returns an error if any of the recorded ticks or inputs is rejected.
+    pub fn play(&mut self) -> Result<Verdict, Error> {
+        while let Some((record, rest)) = self.records.split_first() {
+            self.records = rest;
+            match record {
+                Record::Input(input) => input.apply(&mut self.game)?,
+                Record::Tick { time, hash } => {
+                    self.game.try_tick(*time)?;
+                    let tick = self.ticks;
+                    self.ticks += 1;
+                    let actual = self.game.state_hash();
+                    if actual != *hash {
+                        return Ok(Verdict::Diverged {
+                            tick,
+                            time: *time,
+                            expected: *hash,
+                            actual,
+                        });
+                    }
+                }
+            }
+        }
+        Ok(Verdict::Identical { ticks: self.ticks })
+    }
+}
+
+impl Encode for Input {
+    fn encode(&self, writer: &mut Writer) {
+        match self {
+            Input::AddBall {
+                team,
+                position,
+                angle,
+            } => {
+                0u8.encode(writer);
+                team.encode(writer);
+                position.encode(writer);
+                angle.encode(writer);
+            }
+            Input::RemoveBall { index } => {
+                1u8.encode(writer);
+                (*index as u32).encode(writer);
+            }
+            Input::AddPaddle { team, position } => {
+                2u8.encode(writer);
+                team.encode(writer);
+                position.encode(writer);
+            }
+            Input::RemovePaddle { team } => {
+                3u8.encode(writer);
+                team.encode(writer);
+            }
+            Input::SetPaddleTarget { team, x } => {
+                4u8.encode(writer);
+                team.encode(writer);
+                x.encode(writer);
+            }
+        }
+    }
+
+    fn decode(reader: &mut Reader) -> Result<Self, Error> {
+        match u8::decode(reader)? {
+            0 => Ok(Input::AddBall {
+                team: Encode::decode(reader)?,
+                position: Encode::decode(reader)?,
+                angle: Encode::decode(reader)?,
+            }),
+            1 => Ok(Input::RemoveBall {
+                index: u32::decode(reader)? as usize,
+            }),
+            2 => Ok(Input::AddPaddle {
+                team: Encode::decode(reader)?,
+                position: Encode::decode(reader)?,
+            }),
+            3 => Ok(Input::RemovePaddle {
+                team: Encode::decode(reader)?,
+            }),
+            4 => Ok(Input::SetPaddleTarget {
+                team: Encode::decode(reader)?,
+                x: Encode::decode(reader)?,
+            }),
+            _ => Err(Error::CorruptedSnapshot("Unknown input")),
+        }
+    }
+}
+
+impl Encode for Record {
+    fn encode(&self, writer: &mut Writer) {
+        match self {
+            Record::Input(input) => {
+                0u8.encode(writer);
+                input.encode(writer);
+            }
+            Record::Tick { time, hash } => {
+                1u8.encode(writer);
+                time.encode(writer);
+                hash.encode(writer);
+            }
+        }
+    }
+
+    fn decode(reader: &mut Reader) -> Result<Self, Error> {
+        match u8::decode(reader)? {
+            0 => Input::decode(reader).map(Record::Input),
+            1 => Ok(Record::Tick {
+                time: Encode::decode(reader)?,
+                hash: Encode::decode(reader)?,
+            }),
+            _ => Err(Error::CorruptedSnapshot("Unknown replay record")),
+        }
+    }
+}
+
+impl Encode for Replay {
+    fn encode(&self, writer: &mut Writer) {
+        self.initial.encode(writer);
+        self.records.encode(writer);
+    }
+
+    fn decode(reader: &mut Reader) -> Result<Self, Error> {
+        Ok(Self {
+            initial: Encode::decode(reader)?,
+            records: Encode::decode(reader)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::Board;
+    use crate::config::GameConfig;
+
+    fn record() -> Replay {
+        let viewport_size = Position { x: 320.0, y: 320.0 };
+        let config = GameConfig::builder().seed(11).bounce_jitter(3.0).build();
+        let mut game = Game::new(Board::square(16), 0, viewport_size, config);
+        let mut recorder = ReplayRecorder::new(&game);
+        for time in (16..3000).step_by(16) {
+            recorder.tick(&mut game, time).unwrap();
+            if time == 800 {
+                let input = Input::AddBall {
+                    team: State::LIT,
+                    position: Position { x: 40.0, y: 40.0 },
+                    angle: 75.0,
+                };
+                recorder.input(&mut game, input).unwrap();
+            }
+        }
+        recorder.finish()
+    }
+
+    #[test]
+    fn should_reproduce_recorded_game() {
+        let replay = Replay::from_bytes(&record().to_bytes()).unwrap();
+        let mut player = ReplayPlayer::new(&replay).unwrap();
+
+        // when
+        let verdict = player.play();
+
+        // then
+        assert_eq!(verdict, Ok(Verdict::Identical { ticks: 187 }));
+        assert_eq!(player.game().balls().len(), 3);
+    }
+
+    #[test]
+    fn should_flag_first_divergent_tick() {
+        let mut replay = record();
+        // pretend the ball was added a bit later during the recording.
+        let input = replay
+            .records
+            .iter()
+            .position(|record| matches!(record, Record::Input(_)))
+            .unwrap();
+        replay.records.swap(input, input + 1);
+        let mut player = ReplayPlayer::new(&replay).unwrap();
+
+        // when
+        let verdict = player.play().unwrap();
+
+        // then
+        let Verdict::Diverged { tick, time, .. } = verdict else {
+            panic!("Expected divergence, got {verdict:?}");
+        };
+        assert_eq!((tick, time), (50, 816));
+    }
+
+    #[test]
+    fn should_reproduce_steered_paddles() {
+        let viewport_size = Position { x: 320.0, y: 320.0 };
+        let mut game = Game::new(Board::square(16), 0, viewport_size, GameConfig::default());
+        let mut recorder = ReplayRecorder::new(&game);
+        let paddle = Input::AddPaddle {
+            team: State::DARK,
+            position: Position { x: 160.0, y: 300.0 },
+        };
+        recorder.input(&mut game, paddle).unwrap();
+        for time in (16..2000).step_by(16) {
+            recorder.tick(&mut game, time).unwrap();
+            if time % 400 == 0 {
+                let x = game.balls()[0].position().x;
+                let input = Input::SetPaddleTarget {
+                    team: State::DARK,
+                    x,
+                };
+                recorder.input(&mut game, input).unwrap();
+            }
+        }
+        let replay = Replay::from_bytes(&recorder.finish().to_bytes()).unwrap();
+        let mut player = ReplayPlayer::new(&replay).unwrap();
+
+        // when
+        let verdict = player.play();
+
+        // then
+        assert_eq!(verdict, Ok(Verdict::Identical { ticks: 124 }));
+        assert_eq!(player.game().paddles(), game.paddles());
+    }
+}
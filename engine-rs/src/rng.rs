@@ -0,0 +1,85 @@
+//! Deterministic pseudo-random numbers.
+//!
+//! The engine cannot depend on the platform randomness,
+//! since the same seed has to reproduce the same game on every
+//! platform (including WASM). Hence we use a tiny SplitMix64 generator.
+
+use crate::error::Error;
+use crate::snapshot::{Encode, Reader, Writer};
+
+/// SplitMix64 pseudo-random number generator.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    /// Create a generator from given seed.
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    /// Return the next 64 random bits.
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Return a random number in `0..bound`.
+    ///
+    /// Panics if the `bound` is zero.
+    pub fn below(&mut self, bound: u64) -> u64 {
+        assert!(bound > 0, "The bound has to be positive");
+        // multiply-shift maps the bits uniformly enough for our purposes.
+        ((self.next_u64() as u128 * bound as u128) >> 64) as u64
+    }
+
+    /// Return a random number in `0.0..1.0`.
+    pub fn next_f32(&mut self) -> f32 {
+        // 24 bits is the precision of `f32` mantissa.
+        (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32
+    }
+}
+
+impl Encode for Rng {
+    fn encode(&self, writer: &mut Writer) {
+        self.state.encode(writer);
+    }
+
+    fn decode(reader: &mut Reader) -> Result<Self, Error> {
+        u64::decode(reader).map(Self::new)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_reproduce_the_sequence_for_the_same_seed() {
+        let mut a = Rng::new(42);
+        let mut b = Rng::new(42);
+        let mut c = Rng::new(43);
+
+        let a: Vec<_> = (0..8).map(|_| a.next_u64()).collect();
+        let b: Vec<_> = (0..8).map(|_| b.next_u64()).collect();
+        let c: Vec<_> = (0..8).map(|_| c.next_u64()).collect();
+
+        assert_eq!(a, b);
+        assert_ne!(a, c);
+    }
+
+    #[test]
+    fn should_stay_within_bounds() {
+        let mut rng = Rng::new(7);
+
+        for _ in 0..1000 {
+            assert!(rng.below(10) < 10);
+            let f = rng.next_f32();
+            assert!((0.0..1.0).contains(&f));
+        }
+    }
+}
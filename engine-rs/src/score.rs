@@ -0,0 +1,75 @@
+//! Scores and the end of the game.
+//!
+//! The game keeps running until one of the end conditions
+//! configured in [`crate::config::GameConfig`] is met.
+
+use crate::board::State;
+use crate::error::Error;
+use crate::snapshot::{Encode, Reader, Writer};
+
+/// Current status of the game.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum GameStatus {
+    /// The game is still being played.
+    Running,
+    /// One of the end conditions is met.
+    Finished {
+        /// The winning team, `None` in case of a draw.
+        winner: Option<State>,
+    },
+}
+
+impl GameStatus {
+    /// Return `true` if the game is over.
+    pub fn is_finished(&self) -> bool {
+        matches!(self, GameStatus::Finished { .. })
+    }
+}
+
+impl Encode for GameStatus {
+    fn encode(&self, writer: &mut Writer) {
+        let winner = match self {
+            GameStatus::Running => None,
+            GameStatus::Finished { winner } => Some(*winner),
+        };
+        winner.encode(writer);
+    }
+
+    fn decode(reader: &mut Reader) -> Result<Self, Error> {
+        Ok(match Option::<Option<State>>::decode(reader)? {
+            None => GameStatus::Running,
+            Some(winner) => GameStatus::Finished { winner },
+        })
+    }
+}
+
+/// Score of a single team.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Score {
+    team: State,
+    cells: u32,
+    flips: u32,
+}
+
+impl Score {
+    pub(crate) fn new(team: State, cells: u32, flips: u32) -> Self {
+        Self { team, cells, flips }
+    }
+
+    /// Return the team.
+    pub fn team(&self) -> State {
+        self.team
+    }
+
+    /// Return the number of cells owned by the team.
+    pub fn cells(&self) -> u32 {
+        self.cells
+    }
+
+    /// Return the number of cells flipped by the balls of the team.
+    pub fn flips(&self) -> u32 {
+        self.flips
+    }
+}
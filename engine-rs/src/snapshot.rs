@@ -0,0 +1,229 @@
+//! Binary snapshots of the game state.
+//!
+//! A snapshot captures everything needed to resume the game
+//! (see [`crate::game::Game::snapshot`] and [`crate::game::Game::restore`]).
+//! The layout is compact and platform independent:
+//!
+//! - 4 bytes of [`MAGIC`] ([`crate::replay::MAGIC`] for replays),
+//! - [`VERSION`] of the layout (`u16`),
+//! - the state itself,
+//! - FNV-1a checksum (`u32`) of all the preceding bytes.
+//!
+//! All the numbers are stored in little endian.
+//! The version has to be bumped whenever the layout changes.
+
+use crate::error::Error;
+
+/// Bytes every snapshot starts with.
+pub const MAGIC: [u8; 4] = *b"WGSN";
+
+/// Current version of the snapshot layout.
+pub const VERSION: u16 = 7;
+
+/// Wrap the encoded state with the header and the checksum.
+pub(crate) fn seal(magic: [u8; 4], state: impl FnOnce(&mut Writer)) -> Vec<u8> {
+    let mut writer = Writer { bytes: Vec::new() };
+    writer.bytes.extend_from_slice(&magic);
+    VERSION.encode(&mut writer);
+    state(&mut writer);
+    let checksum = fnv1a(&writer.bytes);
+    checksum.encode(&mut writer);
+    writer.bytes
+}
+
+/// Validate the header and the checksum and decode the state.
+pub(crate) fn open<T>(
+    magic: [u8; 4],
+    bytes: &[u8],
+    state: impl FnOnce(&mut Reader) -> Result<T, Error>,
+) -> Result<T, Error> {
+    const CHECKSUM_SIZE: usize = std::mem::size_of::<u32>();
+    if bytes.len() < magic.len() + CHECKSUM_SIZE || bytes[..magic.len()] != magic {
+        return Err(Error::CorruptedSnapshot("Not a snapshot"));
+    }
+    let (content, checksum) = bytes.split_at(bytes.len() - CHECKSUM_SIZE);
+    if u32::decode(&mut Reader::new(checksum))? != fnv1a(content) {
+        return Err(Error::CorruptedSnapshot("Checksum mismatch"));
+    }
+    let mut reader = Reader::new(&content[magic.len()..]);
+    let version = u16::decode(&mut reader)?;
+    if version != VERSION {
+        return Err(Error::UnsupportedSnapshotVersion(version));
+    }
+    let value = state(&mut reader)?;
+    if !reader.bytes.is_empty() {
+        return Err(Error::CorruptedSnapshot("Unexpected trailing bytes"));
+    }
+    Ok(value)
+}
+
+/// Compute 64-bit FNV-1a hash of the encoded state.
+pub(crate) fn hash(state: impl FnOnce(&mut Writer)) -> u64 {
+    let mut writer = Writer { bytes: Vec::new() };
+    state(&mut writer);
+    writer
+        .bytes
+        .iter()
+        .fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+            (hash ^ *byte as u64).wrapping_mul(0x0100_0000_01b3)
+        })
+}
+
+/// 32-bit FNV-1a hash.
+fn fnv1a(bytes: &[u8]) -> u32 {
+    bytes.iter().fold(0x811c_9dc5, |hash, byte| {
+        (hash ^ *byte as u32).wrapping_mul(0x0100_0193)
+    })
+}
+
+/// Snapshot output.
+pub(crate) struct Writer {
+    bytes: Vec<u8>,
+}
+
+/// Snapshot input.
+pub(crate) struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes }
+    }
+
+    fn take<const N: usize>(&mut self) -> Result<[u8; N], Error> {
+        if self.bytes.len() < N {
+            return Err(Error::CorruptedSnapshot("Unexpected end of data"));
+        }
+        let (taken, rest) = self.bytes.split_at(N);
+        self.bytes = rest;
+        Ok(taken.try_into().expect("split at N"))
+    }
+}
+
+/// A value that can be stored in a snapshot.
+pub(crate) trait Encode: Sized {
+    /// Append the value to the snapshot.
+    fn encode(&self, writer: &mut Writer);
+
+    /// Read the value from the snapshot.
+    fn decode(reader: &mut Reader) -> Result<Self, Error>;
+}
+
+macro_rules! encode_number {
+    ($($type:ty),*) => {
+        $(
+            impl Encode for $type {
+                fn encode(&self, writer: &mut Writer) {
+                    writer.bytes.extend_from_slice(&self.to_le_bytes());
+                }
+
+                fn decode(reader: &mut Reader) -> Result<Self, Error> {
+                    reader.take().map(<$type>::from_le_bytes)
+                }
+            }
+        )*
+    };
+}
+
+encode_number!(u8, u16, u32, u64, f32);
+
+impl<T: Encode> Encode for Option<T> {
+    fn encode(&self, writer: &mut Writer) {
+        match self {
+            None => 0u8.encode(writer),
+            Some(value) => {
+                1u8.encode(writer);
+                value.encode(writer);
+            }
+        }
+    }
+
+    fn decode(reader: &mut Reader) -> Result<Self, Error> {
+        match u8::decode(reader)? {
+            0 => Ok(None),
+            1 => T::decode(reader).map(Some),
+            _ => Err(Error::CorruptedSnapshot("Invalid optional value")),
+        }
+    }
+}
+
+impl<T: Encode> Encode for Vec<T> {
+    fn encode(&self, writer: &mut Writer) {
+        (self.len() as u32).encode(writer);
+        for item in self {
+            item.encode(writer);
+        }
+    }
+
+    fn decode(reader: &mut Reader) -> Result<Self, Error> {
+        let len = u32::decode(reader)? as usize;
+        // don't trust the length to pre-allocate, the data might be corrupted.
+        let mut items = Vec::with_capacity(len.min(reader.bytes.len()));
+        for _ in 0..len {
+            items.push(T::decode(reader)?);
+        }
+        Ok(items)
+    }
+}
+
+impl<T: Encode + Copy + Default, const N: usize> Encode for [T; N] {
+    fn encode(&self, writer: &mut Writer) {
+        for item in self {
+            item.encode(writer);
+        }
+    }
+
+    fn decode(reader: &mut Reader) -> Result<Self, Error> {
+        let mut items = [T::default(); N];
+        for item in &mut items {
+            *item = T::decode(reader)?;
+        }
+        Ok(items)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_detect_corrupted_data() {
+        let bytes = seal(MAGIC, |writer| {
+            42u64.encode(writer);
+            Some(1.5f32).encode(writer);
+        });
+
+        // when
+        let decoded = open(MAGIC, &bytes, |reader| {
+            Ok((u64::decode(reader)?, Option::<f32>::decode(reader)?))
+        });
+        let mut corrupted = bytes.clone();
+        corrupted[7] ^= 0x10;
+        let corrupted = open(MAGIC, &corrupted, u64::decode);
+        let truncated = open(MAGIC, &bytes[..3], u64::decode);
+
+        // then
+        assert_eq!(decoded, Ok((42, Some(1.5))));
+        assert_eq!(
+            corrupted,
+            Err(Error::CorruptedSnapshot("Checksum mismatch"))
+        );
+        assert_eq!(truncated, Err(Error::CorruptedSnapshot("Not a snapshot")));
+    }
+
+    #[test]
+    fn should_reject_other_versions() {
+        let mut bytes = seal(MAGIC, |writer| 42u64.encode(writer));
+        bytes[4] = 0xff;
+        let content = bytes.len() - 4;
+        let checksum = fnv1a(&bytes[..content]).to_le_bytes();
+        bytes[content..].copy_from_slice(&checksum);
+
+        // when
+        let result = open(MAGIC, &bytes, u64::decode);
+
+        // then
+        assert_eq!(result, Err(Error::UnsupportedSnapshotVersion(0xff)));
+    }
+}